@@ -1,3 +1,4 @@
+use crate::db::DBHelper;
 use colored::Colorize;
 use rustyline::completion::Pair;
 use rustyline::completion::{Completer, FilenameCompleter};
@@ -6,21 +7,110 @@ use rustyline::highlight::Highlighter;
 use rustyline::hint::Hinter;
 use rustyline::validate::Validator;
 use rustyline::{Context, Helper};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+/// How long completed keys are reused for the same prefix
+const KEY_COMPLETION_TTL: Duration = Duration::from_secs(5);
+/// Maximum number of keys offered as completion candidates
+const KEY_COMPLETION_LIMIT: usize = 50;
 
 #[derive(Default, Helper)]
 pub struct CliHelper {
     pub commands: Vec<String>,
     pub filename_completer: FilenameCompleter,
+    pub db_helper: Option<Rc<RefCell<DBHelper>>>,
+    key_cache: RefCell<KeyCompletionCache>,
+}
+
+/// Completed keys per prefix, only valid for one column family and until the next write
+#[derive(Default)]
+struct KeyCompletionCache {
+    cf: String,
+    writes: u64,
+    entries: HashMap<String, (Instant, Vec<String>)>,
 }
 
 impl CliHelper {
-    pub fn new(commands: Vec<String>) -> Self {
+    pub fn new(commands: Vec<String>, db_helper: Rc<RefCell<DBHelper>>) -> Self {
         let filename_completer = FilenameCompleter::new();
         Self {
             commands,
             filename_completer,
+            db_helper: Some(db_helper),
+            key_cache: RefCell::default(),
+        }
+    }
+
+    /// Whether the word at `word_index` of `words` is a key argument
+    fn is_key_argument(words: &[&str], word_index: usize) -> bool {
+        match (words.first(), word_index) {
+            (Some(&("get" | "delete" | "put")), 1) => true,
+            (Some(&("contains-key" | "prefix")), index) if index > 1 => {
+                matches!(words[index - 1], "-k" | "--key" | "-p" | "--prefix")
+            }
+            _ => false,
         }
     }
+
+    /// Whether the word at `word_index` of `words` is a column family name
+    fn is_cf_argument(words: &[&str], word_index: usize) -> bool {
+        match (words.first(), word_index) {
+            (Some(&("use" | "drop-cf" | "diff")), 1) => true,
+            (Some(&("compact" | "stats")), index) if index > 1 => words[index - 1] == "--cf",
+            (Some(&"copy-cf"), index) if index > 1 => {
+                matches!(words[index - 1], "--from" | "--to")
+            }
+            _ => false,
+        }
+    }
+
+    /// Column families starting with `prefix`, read on every completion so created and
+    /// dropped column families show up right away
+    fn complete_cfs(&self, prefix: &str) -> Vec<String> {
+        let Some(db_helper) = &self.db_helper else {
+            return Vec::new();
+        };
+        let Ok(db_helper) = db_helper.try_borrow() else {
+            return Vec::new();
+        };
+        db_helper
+            .cf_list
+            .iter()
+            .filter(|cf| cf.starts_with(prefix))
+            .cloned()
+            .collect()
+    }
+
+    fn complete_keys(&self, prefix: &str) -> Vec<String> {
+        let Some(db_helper) = &self.db_helper else {
+            return Vec::new();
+        };
+        let Ok(db_helper) = db_helper.try_borrow() else {
+            return Vec::new();
+        };
+        let mut cache = self.key_cache.borrow_mut();
+        if cache.cf != db_helper.current_cf || cache.writes != db_helper.writes.get() {
+            cache.cf = db_helper.current_cf.clone();
+            cache.writes = db_helper.writes.get();
+            cache.entries.clear();
+        }
+        cache
+            .entries
+            .retain(|_, (created, _)| created.elapsed() < KEY_COMPLETION_TTL);
+        if let Some((_, keys)) = cache.entries.get(prefix) {
+            return keys.clone();
+        }
+        let keys = db_helper
+            .keys_with_prefix(prefix, KEY_COMPLETION_LIMIT)
+            .unwrap_or_default();
+        cache
+            .entries
+            .insert(prefix.to_string(), (Instant::now(), keys.clone()));
+        keys
+    }
 }
 
 impl Completer for CliHelper {
@@ -32,6 +122,28 @@ impl Completer for CliHelper {
         pos: usize,
         ctx: &Context<'_>,
     ) -> Result<(usize, Vec<Self::Candidate>), ReadlineError> {
+        // 补全命令的 key 参数
+        let words: Vec<&str> = line[..pos].split(' ').collect();
+        let word_index = words.len() - 1;
+        let is_key = Self::is_key_argument(&words, word_index);
+        if is_key || Self::is_cf_argument(&words, word_index) {
+            let prefix = words[word_index];
+            let names = if is_key {
+                self.complete_keys(prefix)
+            } else {
+                self.complete_cfs(prefix)
+            };
+            let candidates: Vec<Pair> = names
+                .into_iter()
+                .map(|key| Pair {
+                    display: key.clone(),
+                    replacement: key,
+                })
+                .collect();
+            if !candidates.is_empty() {
+                return Ok((pos - prefix.len(), candidates));
+            }
+        }
         // 如果行以空格结尾或为空，尝试补全路径
         if line.ends_with(' ') || line.is_empty() {
             return self.filename_completer.complete(line, pos, ctx);
@@ -1,17 +1,39 @@
 use crate::{
-    command::DBCommand,
-    db::DBHelper,
-    display::{print_column_families, print_database_info, print_key_value_list},
-    utility::write_output_to_file,
+    command::{AllCfArgs, DBCommand, HexArgs, Setting, ValueArgs},
+    db::{
+        CountMode, DBHelper, KeyTimestampLayout, RetryIter, SearchPattern, SearchTarget,
+        search_matches,
+    },
+    display::{
+        DisplayOptions, OutputFormat, ValueView, print_cf_diff, print_cf_stats, print_cf_values,
+        print_column_families, print_compaction, print_database_info, print_key_value, print_keys,
+        print_multi_get, print_read_amp, print_ttl_report, print_value_ttl, print_value_view,
+        print_wal_batches, render, render_cf_entries,
+    },
+    error::CliError,
+    utility::{copy_to_clipboard, parse_tab_separated, read_key, write_output_to_file},
+    wal::read_wal,
 };
 use anyhow::Result;
+use clap::Parser;
+use clap::error::ErrorKind;
 use colored::Colorize;
+use dialoguer::theme::ColorfulTheme;
+use dialoguer::{FuzzySelect, Input};
+use rocksdb::WriteOptions;
 use rustyrepl::ReplCommandProcessor;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, IsTerminal};
+use std::path::Path;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
 
 #[derive(Debug)]
 pub struct CliProcessor {
-    pub db_helper: RefCell<DBHelper>,
+    pub db_helper: Rc<RefCell<DBHelper>>,
+    /// Format of listing commands, set with --output-format
+    pub output_format: Cell<OutputFormat>,
 }
 
 impl ReplCommandProcessor<DBCommand> for CliProcessor {
@@ -20,31 +42,178 @@ impl ReplCommandProcessor<DBCommand> for CliProcessor {
     }
     fn process_command(&self, command: DBCommand) -> Result<()> {
         match command {
-            DBCommand::List => {
-                self.handle_list();
+            DBCommand::List { sort } => {
+                self.handle_list(sort);
+            }
+            DBCommand::CreateCf { name } => {
+                self.db_helper.borrow_mut().create_cf(&name)?;
+                println!("Column family {} created", name.bright_green());
+            }
+            DBCommand::CopyCf {
+                from,
+                to,
+                overwrite,
+                batch_size,
+                write,
+            } => {
+                let mut helper = self.db_helper.borrow_mut();
+                let batch_size = batch_size.unwrap_or(helper.batch_size);
+                let (copied, skipped) =
+                    helper.copy_cf(&from, &to, overwrite, batch_size, write.write_options())?;
+                println!(
+                    "Copied {}, Skipped {} (already exist)",
+                    copied.to_string().bright_green(),
+                    skipped
+                );
+            }
+            DBCommand::DropCf { name } => {
+                self.handle_drop_cf(&name)?;
             }
             DBCommand::Use { name } => {
-                self.handle_use(name);
+                self.handle_use(name)?;
             }
-            DBCommand::Delete { key } => {
-                self.handle_delete(&key)?;
+            DBCommand::Put {
+                key,
+                value,
+                write,
+                hex,
+            } => {
+                let key = DBHelper::decode_key(&key, hex.hex_keys)?;
+                let value = DBHelper::decode_key(&value, hex.hex_values)?;
+                self.db_helper
+                    .borrow()
+                    .put(&key, &value, &write.write_options())?;
             }
-
-            DBCommand::Get { key, json } => {
-                self.db_helper.borrow().get(&key, json)?;
+            DBCommand::PutBatch { file, write } => {
+                let content = std::fs::read_to_string(&file)?;
+                let pairs = parse_tab_separated(&content)?;
+                let written = self
+                    .db_helper
+                    .borrow()
+                    .put_batch(pairs, &write.write_options())?;
+                println!(
+                    "Put {} key(s) from {}",
+                    written.to_string().bright_green(),
+                    file
+                );
             }
-            DBCommand::Keys { limit } => {
-                if let Ok(keys) = self.db_helper.borrow().get_keys(limit) {
-                    for key in keys {
-                        println!("{}", key.bright_green());
-                    }
+            DBCommand::DeleteBatch { file, write } => {
+                let content = std::fs::read_to_string(&file)?;
+                let keys = content.lines().filter(|line| !line.is_empty()).collect();
+                let deleted = self
+                    .db_helper
+                    .borrow()
+                    .delete_batch(keys, &write.write_options())?;
+                println!(
+                    "Deleted {} key(s) from {}",
+                    deleted.to_string().bright_green(),
+                    file
+                );
+            }
+            DBCommand::DeleteRange {
+                start,
+                end,
+                dry_run,
+                yes,
+                hex,
+                write,
+            } => {
+                let start = DBHelper::decode_key(&start, hex.hex_keys)?;
+                let end = DBHelper::decode_key(&end, hex.hex_keys)?;
+                let write_options = write.write_options();
+                self.handle_delete_range(&start, &end, dry_run, yes, &write_options)?;
+            }
+            DBCommand::Export { output, format } => {
+                let file = BufWriter::new(File::create(&output)?);
+                let exported = self.db_helper.borrow().export_cf(file, format)?;
+                println!(
+                    "Exported {} entries to {}",
+                    exported.to_string().bright_green(),
+                    output
+                );
+            }
+            DBCommand::Import {
+                file,
+                format,
+                write,
+            } => {
+                let reader = BufReader::new(File::open(&file)?);
+                let imported =
+                    self.db_helper
+                        .borrow()
+                        .import_cf(reader, format, write.write_options())?;
+                println!(
+                    "Imported {} entries from {}",
+                    imported.to_string().bright_green(),
+                    file
+                );
+            }
+            DBCommand::Delete {
+                key,
+                key_file,
+                write,
+                hex,
+            } => {
+                let key = read_key(key, key_file.as_deref(), hex.hex_keys)?;
+                self.handle_delete(&key, &write.write_options())?;
+            }
+
+            DBCommand::Get {
+                key,
+                key_file,
+                json,
+                to_clipboard,
+                probe,
+                show_seq,
+                show_ttl,
+                value_view,
+                all_cf,
+                only_cf,
+                values,
+                hex,
+            } => {
+                let key = read_key(key, key_file.as_deref(), hex.hex_keys)?;
+                let options = self.display_options(values, hex);
+                if all_cf {
+                    let helper = self.db_helper.borrow();
+                    let cfs = helper.select_cfs(only_cf.as_deref())?;
+                    print_cf_values(&helper.get_all_cf(&key, &cfs)?, options);
+                } else if probe {
+                    self.db_helper.borrow().probe(&key, options)?;
+                } else {
+                    self.db_helper.borrow().get(&key, json, show_seq, options)?;
+                }
+                if let Some(view) = value_view {
+                    self.handle_value_view(&key, view)?;
+                }
+                if show_ttl {
+                    self.handle_show_ttl(&key)?;
+                }
+                if to_clipboard {
+                    self.handle_copy_value(&key)?;
                 }
             }
+            DBCommand::Keys {
+                limit,
+                iter_args,
+                hex,
+            } => {
+                let keys =
+                    self.db_helper
+                        .borrow()
+                        .get_keys(limit, iter_args.reverse, iter_args.retry)?;
+                print_keys(
+                    keys.into_iter(),
+                    self.display_options(ValueArgs::default(), hex),
+                );
+                self.report_scan_stats();
+            }
             DBCommand::Info => {
                 print_database_info(
                     &self.db_helper.borrow().db,
                     &self.db_helper.borrow().path,
                     &self.db_helper.borrow().current_cf,
+                    self.output_format.get(),
                 )
                 .unwrap();
             }
@@ -55,31 +224,55 @@ impl ReplCommandProcessor<DBCommand> for CliProcessor {
                 limit,
                 all,
                 output,
+                iter_args,
+                values,
+                hex,
             } => {
-                if let Ok(key_values) = self.db_helper.borrow().prefix(&prefix, with_highlight) {
-                    self.print_or_output_to_file(key_values, all, limit, output.as_deref())?;
-                }
+                let options = self.display_options(values, hex);
+                let prefix = DBHelper::decode_key(&prefix, hex.hex_keys)?;
+                // highlighting would end up in the hex of the key
+                let with_highlight = with_highlight && !hex.hex_keys;
+                let helper = self.db_helper.borrow();
+                let key_values =
+                    helper.prefix(&prefix, with_highlight, iter_args.reverse, iter_args.retry)?;
+                self.print_or_output_to_file(key_values, all, limit, output.as_deref(), options)?;
+                self.report_scan_stats();
             }
 
             DBCommand::Scan {
                 start,
                 end,
-                reverse,
+                iter_args,
                 limit,
                 all,
                 output,
+                values,
+                hex,
             } => {
-                if let Ok(key_values) =
-                    self.db_helper
-                        .borrow()
-                        .scan(start.as_deref(), end.as_deref(), reverse)
-                {
-                    self.print_or_output_to_file(key_values, all, limit, output.as_deref())?;
-                }
+                let options = self.display_options(values, hex);
+                let start = start
+                    .map(|start| DBHelper::decode_key(&start, hex.hex_keys))
+                    .transpose()?;
+                let end = end
+                    .map(|end| DBHelper::decode_key(&end, hex.hex_keys))
+                    .transpose()?;
+                let helper = self.db_helper.borrow();
+                let key_values = helper.scan(
+                    start.as_deref(),
+                    end.as_deref(),
+                    iter_args.reverse,
+                    iter_args.retry,
+                )?;
+                self.print_or_output_to_file(key_values, all, limit, output.as_deref(), options)?;
+                self.report_scan_stats();
             }
 
-            DBCommand::ContainsKey { key } => {
-                if self.db_helper.borrow().contains_stringkey(&key) {
+            DBCommand::ContainsKey { key, hex } => {
+                if self
+                    .db_helper
+                    .borrow()
+                    .contains_key(&DBHelper::decode_key(&key, hex.hex_keys)?)
+                {
                     println!("Key {} exists", key.bright_green());
                 } else {
                     println!("Key {} doesn't exists", key.bright_red());
@@ -88,27 +281,201 @@ impl ReplCommandProcessor<DBCommand> for CliProcessor {
 
             DBCommand::SearchKey {
                 key,
+                regex,
                 with_highlight,
                 limit,
                 all,
                 output,
+                iter_args,
+                values,
+                cf_args,
+                hex,
             } => {
-                if let Ok(key_values) = self.db_helper.borrow().search_key(&key, with_highlight) {
-                    self.print_or_output_to_file(key_values, all, limit, output.as_deref())?;
+                let options = self.display_options(values, hex);
+                // highlighting would end up in the hex of the value
+                let with_highlight = with_highlight && !hex.hex_values;
+                let pattern = SearchPattern::new(&key, regex)?;
+                if cf_args.all_cf {
+                    let limit = if all { usize::MAX } else { limit };
+                    self.handle_all_cf_search(
+                        &cf_args,
+                        iter_args.reverse,
+                        iter_args.retry,
+                        options,
+                        |iter| {
+                            search_matches(iter, &pattern, SearchTarget::Key, with_highlight)
+                                .take(limit)
+                                .collect()
+                        },
+                    )?;
+                } else {
+                    let helper = self.db_helper.borrow();
+                    let key_values = helper.search_key(
+                        &pattern,
+                        with_highlight,
+                        iter_args.reverse,
+                        iter_args.retry,
+                    )?;
+                    self.print_or_output_to_file(
+                        key_values,
+                        all,
+                        limit,
+                        output.as_deref(),
+                        options,
+                    )?;
                 }
+                self.report_scan_stats();
             }
 
             DBCommand::SearchValue {
                 value,
+                regex,
                 with_highlight,
                 limit,
                 all,
                 output,
+                iter_args,
+                values,
+                cf_args,
+                hex,
             } => {
-                if let Ok(key_values) = self.db_helper.borrow().search_value(&value, with_highlight)
-                {
-                    self.print_or_output_to_file(key_values, all, limit, output.as_deref())?;
+                let options = self.display_options(values, hex);
+                // highlighting would end up in the hex of the value
+                let with_highlight = with_highlight && !hex.hex_values;
+                let pattern = SearchPattern::new(&value, regex)?;
+                if cf_args.all_cf {
+                    let limit = if all { usize::MAX } else { limit };
+                    self.handle_all_cf_search(
+                        &cf_args,
+                        iter_args.reverse,
+                        iter_args.retry,
+                        options,
+                        |iter| {
+                            search_matches(iter, &pattern, SearchTarget::Value, with_highlight)
+                                .take(limit)
+                                .collect()
+                        },
+                    )?;
+                } else {
+                    let helper = self.db_helper.borrow();
+                    let key_values = helper.search_value(
+                        &pattern,
+                        with_highlight,
+                        iter_args.reverse,
+                        iter_args.retry,
+                    )?;
+                    self.print_or_output_to_file(
+                        key_values,
+                        all,
+                        limit,
+                        output.as_deref(),
+                        options,
+                    )?;
                 }
+                self.report_scan_stats();
+            }
+            DBCommand::Nth { index } => match self.db_helper.borrow().nth(index)? {
+                Some((key, value)) => print_key_value(
+                    &key,
+                    &value,
+                    self.display_options(ValueArgs::default(), HexArgs::default()),
+                ),
+                None => println!("No entry at index {}", index.to_string().bright_red()),
+            },
+            DBCommand::Count {
+                prefix,
+                start,
+                end,
+                pattern,
+                target,
+                exact,
+            } => {
+                let mode = match (prefix, pattern) {
+                    (Some(prefix), _) => CountMode::Prefix(prefix),
+                    (None, Some(pattern)) => CountMode::Pattern {
+                        pattern: SearchPattern::new(&pattern, false)?,
+                        target,
+                    },
+                    (None, None) if start.is_some() || end.is_some() => {
+                        CountMode::Range { start, end }
+                    }
+                    (None, None) => CountMode::All,
+                };
+                let estimated = matches!(mode, CountMode::All) && !exact;
+                let count = self.db_helper.borrow().count_cf(mode, exact)?;
+                println!(
+                    "Found {}{} entries",
+                    if estimated { "about " } else { "" },
+                    count.to_string().bright_green()
+                );
+            }
+            DBCommand::MultiGet {
+                keys,
+                file,
+                values,
+                hex,
+            } => {
+                let content = match file {
+                    Some(file) => std::fs::read_to_string(file)?,
+                    None => String::new(),
+                };
+                let keys = keys
+                    .iter()
+                    .map(String::as_str)
+                    .chain(content.lines().filter(|line| !line.is_empty()))
+                    .map(|key| DBHelper::decode_key(key, hex.hex_keys))
+                    .collect::<std::result::Result<Vec<_>, _>>()?;
+                let entries = self.db_helper.borrow().multi_get(keys)?;
+                print_multi_get(&entries, self.display_options(values, hex));
+            }
+            DBCommand::Set {
+                setting: Setting::OutputFormat { format },
+            } => {
+                self.output_format.set(format);
+                println!("Output format set to {:?}", format);
+            }
+            DBCommand::Stats { cf, only_cf } => {
+                let helper = self.db_helper.borrow();
+                let cfs = helper.select_cfs(cf.as_deref().or(only_cf.as_deref()))?;
+                for cf in &cfs {
+                    print_cf_stats(cf, &helper.cf_statistics(cf)?);
+                }
+            }
+            DBCommand::Compact { start, end, cf } => {
+                self.handle_compact(cf, start, end)?;
+            }
+            DBCommand::Diff { other, limit, all } => {
+                let helper = self.db_helper.borrow();
+                let entries = helper.diff_cf(&other)?;
+                if all {
+                    print_cf_diff(entries, &helper.current_cf, &other);
+                } else {
+                    print_cf_diff(entries.take(limit), &helper.current_cf, &other);
+                }
+                drop(helper);
+                self.report_scan_stats();
+            }
+            DBCommand::TtlCheck {
+                ts_offset,
+                ts_width,
+                ts_millis,
+                little_endian,
+                ttl,
+                sample,
+            } => {
+                let layout = KeyTimestampLayout {
+                    offset: ts_offset,
+                    width: ts_width,
+                    little_endian,
+                    millis: ts_millis,
+                };
+                self.handle_ttl_check(layout, ttl, sample)?;
+            }
+            DBCommand::ReadAmp { sample } => {
+                print_read_amp(&self.db_helper.borrow().read_amp(sample)?);
+            }
+            DBCommand::WalScan { since, limit } => {
+                self.handle_wal_scan(since, limit)?;
             }
             _ => println!("Unknown command"),
         }
@@ -121,52 +488,366 @@ impl ReplCommandProcessor<DBCommand> for CliProcessor {
 }
 
 impl CliProcessor {
-    pub fn new(db_helper: DBHelper) -> Self {
+    /// Runs each line of the script at `path` as if typed in the shell, skipping empty lines and
+    /// `#` comments, until a quit command. Failing commands are reported and skipped, or end the
+    /// script if `stop_on_error` is set; the result is an error if any command failed. A summary
+    /// of the commands run and keys written is printed at the end unless `quiet` is set
+    pub fn run_script(&self, path: &Path, stop_on_error: bool, quiet: bool) -> Result<()> {
+        let content = std::fs::read_to_string(path)?;
+        let keys_before = self.db_helper.borrow().keys_written.get();
+        let (mut run, mut failed) = (0, 0);
+        for (index, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            match self.run_line(line) {
+                Ok(true) => run += 1,
+                Ok(false) => break,
+                Err(e) => {
+                    eprintln!("{} line {}: {}", path.display(), index + 1, e);
+                    run += 1;
+                    failed += 1;
+                    if stop_on_error {
+                        break;
+                    }
+                }
+            }
+        }
+        if !quiet {
+            let keys_written = self.db_helper.borrow().keys_written.get() - keys_before;
+            self.print_status(&format!(
+                "Script {} finished",
+                path.display().to_string().bright_green()
+            ));
+            self.print_status(&format!("  Commands run: {}", run));
+            self.print_status(&format!(
+                "  Succeeded:    {}",
+                (run - failed).to_string().bright_green()
+            ));
+            self.print_status(&format!(
+                "  Failed:       {}",
+                failed.to_string().bright_red()
+            ));
+            self.print_status(&format!("  Keys written: {}", keys_written));
+        }
+        if failed > 0 {
+            anyhow::bail!("{} command(s) of {} failed", failed, path.display());
+        }
+        Ok(())
+    }
+
+    /// Parses and runs one command line like the shell does. Returns `false` for a quit command
+    pub fn run_line(&self, line: &str) -> Result<bool> {
+        let mut words = shell_words::split(line)?;
+        let Some(command) = words.first().cloned() else {
+            return Ok(true);
+        };
+        if self.is_quit(&command.to_lowercase()) {
+            return Ok(false);
+        }
+        // clap parses from the second word, as after the binary name on a command line
+        words.insert(0, command);
+        match DBCommand::try_parse_from(words) {
+            Ok(command) => self.process_command(command)?,
+            Err(e) => match e.kind() {
+                ErrorKind::DisplayHelp | ErrorKind::DisplayVersion => println!("{}", e),
+                _ => return Err(e.into()),
+            },
+        }
+        Ok(true)
+    }
+
+    pub fn new(db_helper: Rc<RefCell<DBHelper>>, output_format: OutputFormat) -> Self {
         Self {
-            db_helper: RefCell::new(db_helper),
+            db_helper,
+            output_format: Cell::new(output_format),
+        }
+    }
+
+    fn display_options(&self, values: ValueArgs, hex: HexArgs) -> DisplayOptions {
+        DisplayOptions {
+            show_raw: values.show_raw,
+            format: self.output_format.get(),
+            decode_value: values.decode_value,
+            hex_keys: hex.hex_keys,
+            hex_values: hex.hex_values,
+        }
+    }
+
+    fn handle_list(&self, sort: bool) {
+        let helper = self.db_helper.borrow();
+        let mut cfs = helper.cf_list.clone();
+        if sort {
+            cfs.sort();
+        }
+        print_column_families(&cfs, &helper.current_cf, self.output_format.get());
+    }
+
+    fn handle_use(&self, name: Option<String>) -> Result<()> {
+        let name = match name {
+            Some(name) => name,
+            None => match self.pick_cf()? {
+                Some(name) => name,
+                None => return Ok(()),
+            },
+        };
+        if !self.db_helper.borrow().cf_list.contains(&name) {
+            return Err(CliError::CfNotFound(name).into());
+        }
+        self.db_helper.borrow_mut().current_cf = name.clone();
+        println!("DB switched to column family {}", name.bright_green());
+        Ok(())
+    }
+
+    /// Fuzzy picker over the column families, only lists them when not running in a terminal
+    fn pick_cf(&self) -> Result<Option<String>> {
+        let helper = self.db_helper.borrow();
+        if !std::io::stdin().is_terminal() || !std::io::stdout().is_terminal() {
+            print_column_families(
+                &helper.cf_list,
+                &helper.current_cf,
+                self.output_format.get(),
+            );
+            return Ok(None);
+        }
+        let current = helper
+            .cf_list
+            .iter()
+            .position(|cf| *cf == helper.current_cf)
+            .unwrap_or(0);
+        let selection = FuzzySelect::with_theme(&ColorfulTheme::default())
+            .with_prompt("Column family")
+            .items(&helper.cf_list)
+            .default(current)
+            .interact_opt()?;
+        Ok(selection.map(|index| helper.cf_list[index].clone()))
+    }
+
+    fn handle_drop_cf(&self, name: &str) -> Result<()> {
+        let mut helper = self.db_helper.borrow_mut();
+        helper.drop_cf(name)?;
+        println!("Column family {} dropped", name.bright_red());
+        if helper.current_cf == name {
+            helper.current_cf = "default".to_string();
+            println!(
+                "DB switched to column family {}",
+                helper.current_cf.bright_green()
+            );
+        }
+        Ok(())
+    }
+
+    fn handle_copy_value(&self, key: &[u8]) -> Result<()> {
+        if let Some(value) = self.db_helper.borrow().get_value(key)? {
+            copy_to_clipboard(&String::from_utf8_lossy(&value))?;
+            println!(
+                "Copied {} bytes to the clipboard",
+                value.len().to_string().bright_green()
+            );
+        }
+        Ok(())
+    }
+
+    fn handle_value_view(&self, key: &[u8], view: ValueView) -> Result<()> {
+        if let Some(value) = self.db_helper.borrow().get_value(key)? {
+            print_value_view(&value, view)?;
         }
+        Ok(())
     }
 
-    fn handle_list(&self) {
-        print_column_families(
-            &self.db_helper.borrow().cf_list,
-            &self.db_helper.borrow().current_cf,
+    fn handle_show_ttl(&self, key: &[u8]) -> Result<()> {
+        let helper = self.db_helper.borrow();
+        let Some(ttl) = helper.ttl else {
+            anyhow::bail!("The database wasn't opened in TTL mode, pass --ttl");
+        };
+        let Some(value) = helper.get_stored_value(key)? else {
+            return Ok(());
+        };
+        match KeyTimestampLayout::ttl_suffix(&value).decode(&value) {
+            Some(written) => print_value_ttl(written, ttl),
+            None => println!("{}", "The value has no TTL timestamp".bright_red()),
+        }
+        Ok(())
+    }
+
+    fn handle_delete(&self, key: &[u8], write_options: &WriteOptions) -> Result<()> {
+        self.db_helper.borrow_mut().delete(key, write_options)?;
+        println!("Key {} deleted", String::from_utf8_lossy(key).bright_green());
+        Ok(())
+    }
+
+    fn handle_delete_range(
+        &self,
+        start: &[u8],
+        end: &[u8],
+        dry_run: bool,
+        yes: bool,
+        write_options: &WriteOptions,
+    ) -> Result<()> {
+        let helper = self.db_helper.borrow();
+        if dry_run {
+            let count = helper.scan(Some(start), Some(end), false, 0)?.count();
+            println!(
+                "{} entries would be deleted",
+                count.to_string().bright_green()
+            );
+            return Ok(());
+        }
+        if helper.readonly {
+            return Err(CliError::ReadOnly.into());
+        }
+        if !yes {
+            if !std::io::stdin().is_terminal() {
+                anyhow::bail!("Not a terminal to confirm the delete in, pass --yes");
+            }
+            let answer: String = Input::with_theme(&ColorfulTheme::default())
+                .with_prompt(format!(
+                    "Delete every key from {} to {} in column family {}? Type yes to confirm",
+                    String::from_utf8_lossy(start),
+                    String::from_utf8_lossy(end),
+                    helper.current_cf
+                ))
+                .allow_empty(true)
+                .interact_text()?;
+            if answer != "yes" {
+                println!("Delete range aborted");
+                return Ok(());
+            }
+        }
+        helper.delete_range(start, end, write_options)?;
+        println!(
+            "Deleted every key from {} to {}",
+            String::from_utf8_lossy(start).bright_red(),
+            String::from_utf8_lossy(end).bright_red()
         );
+        Ok(())
     }
 
-    fn handle_use(&self, name: String) {
-        if self.db_helper.borrow().cf_list.contains(&name) {
-            self.db_helper.borrow_mut().current_cf = name.clone();
-            println!("DB switched to column family {}", name.bright_green());
-        } else {
-            println!("No column family {} selected", name.bright_red());
+    fn handle_compact(
+        &self,
+        cf: Option<String>,
+        start: Option<String>,
+        end: Option<String>,
+    ) -> Result<()> {
+        let helper = self.db_helper.borrow();
+        let cf = cf.unwrap_or_else(|| helper.current_cf.clone());
+        println!("Compaction of {} started...", cf.bright_green());
+        let started = Instant::now();
+        let (before, after) = helper.compact(
+            &cf,
+            start.as_deref().map(str::as_bytes),
+            end.as_deref().map(str::as_bytes),
+        )?;
+        print_compaction(&cf, &before, &after, started.elapsed());
+        Ok(())
+    }
+
+    fn handle_ttl_check(
+        &self,
+        layout: KeyTimestampLayout,
+        ttl: Option<u64>,
+        sample: usize,
+    ) -> Result<()> {
+        if !matches!(layout.width, 4 | 8) {
+            anyhow::bail!("Timestamp width must be 4 or 8 bytes");
         }
+        let helper = self.db_helper.borrow();
+        let Some(ttl) = ttl.map(Duration::from_secs).or(helper.ttl) else {
+            anyhow::bail!("No TTL configured, pass --ttl");
+        };
+        let entries = helper.key_timestamps(layout, sample)?;
+        print_ttl_report(&entries, ttl);
+        Ok(())
     }
 
-    fn handle_delete(&self, key: &str) -> Result<()> {
-        self.db_helper.borrow_mut().delete(key)?;
-        println!("Key {} deleted", key.bright_green());
+    fn handle_wal_scan(&self, since: u64, limit: usize) -> Result<()> {
+        let helper = self.db_helper.borrow();
+        let batches = read_wal(&helper.db, since)?;
+        print_wal_batches(batches.take(limit).map_while(|batch| match batch {
+            Ok(batch) => Some(batch),
+            Err(e) => {
+                eprintln!("Error occurred while reading the WAL: {}", e);
+                None
+            }
+        }));
         Ok(())
     }
 
+    /// Runs `matches` over the column families selected by `cf_args` and prints the entries
+    /// found in each, in the order of the column family list
+    fn handle_all_cf_search(
+        &self,
+        cf_args: &AllCfArgs,
+        reverse: bool,
+        retry: usize,
+        options: DisplayOptions,
+        matches: impl Fn(RetryIter<'_>) -> Vec<(Vec<u8>, Vec<u8>)> + Sync,
+    ) -> Result<()> {
+        let helper = self.db_helper.borrow();
+        let cfs = helper.select_cfs(cf_args.only_cf.as_deref())?;
+        let results = helper.scan_cfs(&cfs, cf_args.concurrency, reverse, retry, matches)?;
+        drop(helper);
+        render_cf_entries(results, options);
+        Ok(())
+    }
+
+    /// Prints a status line to stdout with tables, to stderr with the other formats so their
+    /// output stays parseable
+    fn print_status(&self, line: &str) {
+        if self.output_format.get() == OutputFormat::Table {
+            println!("{}", line);
+        } else {
+            eprintln!("{}", line);
+        }
+    }
+
+    fn report_scan_stats(&self) {
+        let helper = self.db_helper.borrow();
+        let stats = &helper.scan_stats;
+        let (errors, recovered, skipped) = (
+            stats.errors.get(),
+            stats.recovered.get(),
+            stats.skipped.get(),
+        );
+        if errors == 0 {
+            return;
+        }
+        let mut lines = vec![format!(
+            "Recovered {} entries after {} iterator error(s)",
+            recovered.to_string().bright_green(),
+            errors
+        )];
+        if skipped > 0 {
+            lines.push(format!(
+                "Skipped {} unreadable entries, the scan stopped there and the output is incomplete",
+                skipped.to_string().bright_red()
+            ));
+        }
+        for line in lines {
+            self.print_status(&line);
+        }
+    }
+
     fn print_or_output_to_file<T: Iterator<Item = (Vec<u8>, Vec<u8>)>>(
         &self,
         key_values: T,
         all: bool,
         limit: usize,
         output: Option<&str>,
+        options: DisplayOptions,
     ) -> Result<()> {
         if let Some(out_file) = output {
             if all {
-                write_output_to_file(key_values, &out_file)?;
+                write_output_to_file(key_values, &out_file, options.show_raw)?;
             } else {
-                write_output_to_file(key_values.take(limit), &out_file)?;
+                write_output_to_file(key_values.take(limit), &out_file, options.show_raw)?;
             }
         } else {
             if all {
-                print_key_value_list(key_values);
+                render(key_values, options);
             } else {
-                print_key_value_list(key_values.take(limit));
+                render(key_values.take(limit), options);
             }
         }
         Ok(())
@@ -1,4 +1,10 @@
-use clap::Parser;
+use clap::{Args, Parser, Subcommand};
+
+use crate::db::{DEFAULT_BATCH_SIZE, DEFAULT_PREFIX_LEN, SearchTarget, write_options};
+use crate::display::{IntDecoding, OutputFormat, ValueView};
+use crate::export::ExportFormat;
+use rocksdb::WriteOptions;
+use std::path::PathBuf;
 // use clap::Subcommand;
 
 #[derive(Debug, Parser)]
@@ -9,6 +15,111 @@ pub struct Cli {
     pub path: String,
     #[arg(default_value = "true")]
     pub readonly: Option<bool>,
+    /// TTL in seconds. A writable database is opened in TTL mode, so older entries are dropped on compaction
+    #[arg(long)]
+    pub ttl: Option<u64>,
+    /// Number of operations bulk writes commit per batch
+    #[arg(long, default_value_t = DEFAULT_BATCH_SIZE)]
+    pub batch_size: usize,
+    /// Length of the fixed prefix extractor the database was created with
+    #[arg(long, default_value_t = DEFAULT_PREFIX_LEN)]
+    pub prefix_len: usize,
+    /// Open the database without a prefix extractor, prefix scans then seek in total order
+    #[arg(long, default_value_t = false, conflicts_with = "prefix_len")]
+    pub no_prefix_extractor: bool,
+    /// Output format of listing commands
+    #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+    pub output_format: OutputFormat,
+    /// Disable colored output
+    #[arg(long, default_value_t = false)]
+    pub no_color: bool,
+    /// Run the commands of a file, one per line, instead of starting the shell. Empty lines
+    /// and lines starting with `#` are skipped
+    #[arg(long)]
+    pub script: Option<PathBuf>,
+    /// Run one command instead of starting the shell
+    #[arg(long, conflicts_with = "script")]
+    pub exec: Option<String>,
+    /// Stop the script at the first failing command
+    #[arg(long, default_value_t = false, requires = "script")]
+    pub stop_on_error: bool,
+    /// Don't print the summary at the end of the script
+    #[arg(long, default_value_t = false, requires = "script")]
+    pub quiet: bool,
+}
+
+/// Settings changed with `set`
+#[derive(Debug, Subcommand)]
+pub enum Setting {
+    /// Output format of listing commands
+    OutputFormat {
+        #[arg(value_enum)]
+        format: OutputFormat,
+    },
+}
+
+/// Options of commands that can run over several column families
+#[derive(Debug, Args)]
+pub struct AllCfArgs {
+    /// Run over every column family instead of the current one
+    #[arg(long, default_value_t = false)]
+    pub all_cf: bool,
+    /// Comma-separated column families --all-cf is restricted to
+    #[arg(long, requires = "all_cf")]
+    pub only_cf: Option<String>,
+    /// Number of column families read in parallel with --all-cf
+    #[arg(long, default_value_t = 1)]
+    pub concurrency: usize,
+}
+
+/// Hex input and output for binary keys and values
+#[derive(Debug, Default, Clone, Copy, Args)]
+pub struct HexArgs {
+    /// Key arguments are hex encoded, keys are shown as hex
+    #[arg(long, default_value_t = false)]
+    pub hex_keys: bool,
+    /// Value arguments are hex encoded, values are shown as hex
+    #[arg(long, default_value_t = false)]
+    pub hex_values: bool,
+}
+
+/// Durability of the writes of mutating commands
+#[derive(Debug, Default, Clone, Copy, Args)]
+pub struct WriteArgs {
+    /// Sync the write-ahead log before returning
+    #[arg(long, default_value_t = false)]
+    pub sync: bool,
+    /// Skip the write-ahead log, the write is lost if the process crashes before a flush
+    #[arg(long, default_value_t = false, conflicts_with = "sync")]
+    pub disable_wal: bool,
+}
+
+impl WriteArgs {
+    pub fn write_options(self) -> WriteOptions {
+        write_options(self.sync, self.disable_wal)
+    }
+}
+
+/// How values are shown next to the unescaped text
+#[derive(Debug, Default, Clone, Copy, Args)]
+pub struct ValueArgs {
+    /// Add a column with the raw value, before unescaping
+    #[arg(long, default_value_t = false)]
+    pub show_raw: bool,
+    /// Show values as integers, e.g. int:be:8 or uint:le:4. Values of another width are shown as usual
+    #[arg(long)]
+    pub decode_value: Option<IntDecoding>,
+}
+
+/// Order and read error handling of commands iterating a column family
+#[derive(Debug, Default, Clone, Copy, Args)]
+pub struct IterArgs {
+    /// Iterate in descending key order
+    #[arg(short, long, default_value_t = false)]
+    pub reverse: bool,
+    /// Retry up to N times on a read error, resuming after the last good key
+    #[arg(long, default_value_t = 0)]
+    pub retry: usize,
 }
 
 // #[derive(Debug, Parser)]
@@ -21,31 +132,118 @@ pub struct Cli {
 #[derive(Debug, Parser)]
 pub enum DBCommand {
     /// List all column families
-    List,
+    List {
+        /// Sort the column families by name
+        #[arg(short, long, default_value_t = false)]
+        sort: bool,
+    },
     /// Get information about the database
     Info,
-    /// Switch to a different column family
-    Use { name: String },
+    /// Change a setting for the rest of the session
+    Set {
+        #[command(subcommand)]
+        setting: Setting,
+    },
+    /// Print RocksDB statistics of each column family
+    Stats {
+        /// Only print the statistics of this column family
+        #[arg(long, conflicts_with = "only_cf")]
+        cf: Option<String>,
+        /// Comma-separated column families the statistics are restricted to
+        #[arg(long)]
+        only_cf: Option<String>,
+    },
+    /// Create a column family
+    CreateCf { name: String },
+    /// Drop a column family and all its data
+    DropCf { name: String },
+    /// Copy every entry of a column family into another one, created if missing
+    CopyCf {
+        #[arg(long)]
+        from: String,
+        #[arg(long)]
+        to: String,
+        /// Replace keys that already exist in the destination instead of skipping them
+        #[arg(long, default_value_t = false)]
+        overwrite: bool,
+        /// Number of entries written per batch, defaults to the --batch-size the shell was started with
+        #[arg(long)]
+        batch_size: Option<usize>,
+        #[command(flatten)]
+        write: WriteArgs,
+    },
+    /// Switch to a different column family, pick it interactively if no name is given
+    Use { name: Option<String> },
     /// Get value for a key
     Get {
-        key: String,
+        #[arg(required_unless_present = "key_file")]
+        key: Option<String>,
+        /// Read the raw bytes of the key from a file
+        #[arg(long, conflicts_with = "key")]
+        key_file: Option<String>,
         #[arg(short, long, default_value_t = false)]
         json: bool,
+        /// Copy the value to the system clipboard, needs the `clipboard` feature
+        #[arg(long, default_value_t = false)]
+        to_clipboard: bool,
+        /// Only check the block cache and memtables, without reading from disk
+        #[arg(long, default_value_t = false, conflicts_with_all = ["json", "show_seq"])]
+        probe: bool,
+        /// Show the sequence number of the latest write to the key, read from the WAL
+        #[arg(long, default_value_t = false)]
+        show_seq: bool,
+        /// Show when the key was written and when it expires, needs --ttl
+        #[arg(long, default_value_t = false, conflicts_with = "probe")]
+        show_ttl: bool,
+        /// Show the value decoded as a structure
+        #[arg(long, value_enum, conflicts_with = "probe")]
+        value_view: Option<ValueView>,
+        /// Look the key up in every column family
+        #[arg(long, default_value_t = false, conflicts_with_all = ["json", "probe", "show_seq", "show_ttl", "to_clipboard", "value_view"])]
+        all_cf: bool,
+        /// Comma-separated column families the --all-cf lookup is restricted to
+        #[arg(long, requires = "all_cf")]
+        only_cf: Option<String>,
+        #[command(flatten)]
+        values: ValueArgs,
+        #[command(flatten)]
+        hex: HexArgs,
+    },
+    /// Get several keys of the current column family in one batched read
+    MultiGet {
+        #[arg(required_unless_present = "file", conflicts_with = "file")]
+        keys: Vec<String>,
+        /// File with one key per line
+        #[arg(short, long)]
+        file: Option<String>,
+        #[command(flatten)]
+        values: ValueArgs,
+        #[command(flatten)]
+        hex: HexArgs,
     },
     ///get all the keys of the current column family
     Keys {
         #[arg(short, long, default_value_t = 10000)]
         limit: usize,
+        #[command(flatten)]
+        iter_args: IterArgs,
+        #[command(flatten)]
+        hex: HexArgs,
     },
 
     ContainsKey {
         #[arg(short, long)]
         key: String,
+        #[command(flatten)]
+        hex: HexArgs,
     },
 
     SearchValue {
         #[arg(short, long)]
         value: String,
+        /// Treat the value pattern as a regular expression
+        #[arg(short = 'x', long, default_value_t = false)]
+        regex: bool,
         /// Highlight matched keys, use --with-highlight/-w to highlight matched values
         #[arg(short, long, default_value_t = false)]
         with_highlight: bool,
@@ -55,13 +253,24 @@ pub enum DBCommand {
         /// search all the values that match the given key without limit
         #[arg(short, long, default_value_t = false)]
         all: bool,
-        #[arg(short, long)]
+        #[arg(short, long, conflicts_with = "all_cf")]
         output: Option<String>,
+        #[command(flatten)]
+        iter_args: IterArgs,
+        #[command(flatten)]
+        values: ValueArgs,
+        #[command(flatten)]
+        cf_args: AllCfArgs,
+        #[command(flatten)]
+        hex: HexArgs,
     },
 
     SearchKey {
         #[arg(short, long)]
         key: String,
+        /// Treat the key pattern as a regular expression
+        #[arg(short = 'x', long, default_value_t = false)]
+        regex: bool,
         /// Highlight matched keys, use --with-highlight/-w to highlight matched keys
         #[arg(short, long, default_value_t = false)]
         with_highlight: bool,
@@ -71,13 +280,87 @@ pub enum DBCommand {
         /// search all the keys that match the given key without limit
         #[arg(short, long, default_value_t = false)]
         all: bool,
-        #[arg(short, long)]
+        #[arg(short, long, conflicts_with = "all_cf")]
         output: Option<String>,
+        #[command(flatten)]
+        iter_args: IterArgs,
+        #[command(flatten)]
+        values: ValueArgs,
+        #[command(flatten)]
+        cf_args: AllCfArgs,
+        #[command(flatten)]
+        hex: HexArgs,
     },
     /// Put a key-value pair
-    Put { key: String, value: String },
+    Put {
+        key: String,
+        value: String,
+        #[command(flatten)]
+        write: WriteArgs,
+        #[command(flatten)]
+        hex: HexArgs,
+    },
     /// Delete a key
-    Delete { key: String },
+    Delete {
+        #[arg(required_unless_present = "key_file")]
+        key: Option<String>,
+        /// Read the raw bytes of the key from a file
+        #[arg(long, conflicts_with = "key")]
+        key_file: Option<String>,
+        #[command(flatten)]
+        write: WriteArgs,
+        #[command(flatten)]
+        hex: HexArgs,
+    },
+    /// Put the key-value pairs of a file in one atomic batch
+    PutBatch {
+        /// File with one `key<TAB>value` pair per line
+        #[arg(short, long)]
+        file: String,
+        #[command(flatten)]
+        write: WriteArgs,
+    },
+    /// Delete the keys of a file in one atomic batch
+    DeleteBatch {
+        /// File with one key per line
+        #[arg(short, long)]
+        file: String,
+        #[command(flatten)]
+        write: WriteArgs,
+    },
+    /// Delete every key from start (inclusive) to end (exclusive) with one range tombstone
+    DeleteRange {
+        #[arg(short, long)]
+        start: String,
+        #[arg(short, long)]
+        end: String,
+        /// Only print how many entries would be deleted
+        #[arg(long, default_value_t = false)]
+        dry_run: bool,
+        /// Skip the confirmation prompt
+        #[arg(short, long, default_value_t = false)]
+        yes: bool,
+        #[command(flatten)]
+        hex: HexArgs,
+        #[command(flatten)]
+        write: WriteArgs,
+    },
+    /// Export every entry of the current column family to a file
+    Export {
+        #[arg(short, long)]
+        output: String,
+        #[arg(short, long, value_enum, default_value_t = ExportFormat::Json)]
+        format: ExportFormat,
+    },
+    /// Import the entries of an exported file into the current column family
+    Import {
+        #[arg(long)]
+        file: String,
+        #[arg(short, long, value_enum, default_value_t = ExportFormat::Json)]
+        format: ExportFormat,
+        #[command(flatten)]
+        write: WriteArgs,
+    },
     /// Scan key-value pairs
     Scan {
         /// Start key (inclusive)
@@ -86,8 +369,8 @@ pub enum DBCommand {
         /// End key (exclusive)
         #[arg(short, long)]
         end: Option<String>,
-        #[arg(short, long, default_value_t = false)]
-        reverse: bool,
+        #[command(flatten)]
+        iter_args: IterArgs,
         /// Maximum number of keys to return
         #[arg(short, long, default_value_t = 100)]
         limit: usize,
@@ -95,6 +378,10 @@ pub enum DBCommand {
         all: bool,
         #[arg(short, long)]
         output: Option<String>,
+        #[command(flatten)]
+        values: ValueArgs,
+        #[command(flatten)]
+        hex: HexArgs,
     },
     Prefix {
         /// Prefix to scan
@@ -110,6 +397,96 @@ pub enum DBCommand {
         all: bool,
         #[arg(short, long)]
         output: Option<String>,
+        #[command(flatten)]
+        iter_args: IterArgs,
+        #[command(flatten)]
+        values: ValueArgs,
+        #[command(flatten)]
+        hex: HexArgs,
+    },
+    /// Show the entry at a 0-based position in key order. This walks the column family from
+    /// the start, so it takes O(index) time
+    Nth {
+        #[arg(short, long)]
+        index: usize,
+    },
+    /// Count the entries of the current column family, or those in a prefix, range or matching a pattern
+    Count {
+        #[arg(short, long, conflicts_with_all = ["start", "end", "pattern"])]
+        prefix: Option<String>,
+        /// Start key (inclusive)
+        #[arg(short, long, conflicts_with = "pattern")]
+        start: Option<String>,
+        /// End key (exclusive)
+        #[arg(short, long, conflicts_with = "pattern")]
+        end: Option<String>,
+        /// Count the entries containing the pattern
+        #[arg(long)]
+        pattern: Option<String>,
+        /// Whether --pattern is searched in keys or values
+        #[arg(long = "in", value_enum, default_value_t = SearchTarget::Key, requires = "pattern")]
+        target: SearchTarget,
+        /// Iterate the whole column family instead of using RocksDB's estimate
+        #[arg(long, default_value_t = false)]
+        exact: bool,
+    },
+    /// Compact a key range of a column family, or the whole column family without bounds
+    Compact {
+        /// Start key (inclusive)
+        #[arg(short, long)]
+        start: Option<String>,
+        /// End key (exclusive)
+        #[arg(short, long)]
+        end: Option<String>,
+        /// Column family to compact instead of the current one
+        #[arg(long)]
+        cf: Option<String>,
+    },
+    /// Compare the current column family with another one
+    Diff {
+        /// Column family to compare against
+        other: String,
+        /// Maximum number of differences to show
+        #[arg(short, long, default_value_t = 100)]
+        limit: usize,
+        #[arg(short, long, default_value_t = false)]
+        all: bool,
+    },
+    /// Check the age of sampled keys against the TTL, using a timestamp embedded in the key
+    TtlCheck {
+        /// Byte offset of the timestamp in the key
+        #[arg(long, default_value_t = 0)]
+        ts_offset: usize,
+        /// Width of the timestamp in bytes, 4 or 8
+        #[arg(long, default_value_t = 8)]
+        ts_width: usize,
+        /// The timestamp counts milliseconds instead of seconds
+        #[arg(long, default_value_t = false)]
+        ts_millis: bool,
+        /// The timestamp is little-endian instead of big-endian
+        #[arg(long, default_value_t = false)]
+        little_endian: bool,
+        /// TTL in seconds, defaults to the --ttl the database was opened with
+        #[arg(long)]
+        ttl: Option<u64>,
+        /// Number of keys to check, from the start of the column family
+        #[arg(short, long, default_value_t = 20)]
+        sample: usize,
+    },
+    /// Read a sample of keys and report the block reads and bloom filter checks they caused
+    ReadAmp {
+        /// Number of keys to read, from the start of the column family
+        #[arg(short, long, default_value_t = 100)]
+        sample: usize,
+    },
+    /// Show the operations recorded in the write-ahead log
+    WalScan {
+        /// Sequence number to start reading from
+        #[arg(short, long, default_value_t = 0)]
+        since: u64,
+        /// Maximum number of write batches to show
+        #[arg(short, long, default_value_t = 100)]
+        limit: usize,
     },
     /// Exit the program
     Exit,
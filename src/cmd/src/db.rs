@@ -1,40 +1,541 @@
-use anyhow::Result;
 use colored::Colorize;
+use regex::Regex;
+use rocksdb::ColumnFamilyDescriptor;
 use rocksdb::DB;
+use rocksdb::DBIteratorWithThreadMode;
+use rocksdb::Direction;
 use rocksdb::IteratorMode;
 use rocksdb::Options;
+use rocksdb::ReadOptions;
 use rocksdb::SliceTransform;
+use rocksdb::WriteBatch;
+use rocksdb::WriteOptions;
+use rocksdb::perf::{PerfContext, PerfMetric, PerfStatsLevel, set_perf_stats};
+use std::cell::{Cell, RefCell};
+use std::cmp::Ordering;
+use std::io::{BufRead, Write};
+use std::ops::RangeInclusive;
+use std::sync::atomic::{self, AtomicUsize};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use crate::display::print_key_value;
-use crate::utility::highlight_pattern;
+use crate::display::{DisplayOptions, print_key_value, print_missing_key};
+use crate::error::{CliError, Result};
+use crate::export::{ExportFormat, RecordWriter, read_records};
+use crate::utility::{highlight_pattern, highlight_regex};
+use crate::wal::read_wal;
 #[derive(Debug)]
 pub struct DBHelper {
     pub db: rocksdb::DB,
     pub path: String,
     pub current_cf: String,
     pub cf_list: Vec<String>,
+    /// Iterator error counters of the last scan
+    pub scan_stats: ScanStats,
+    /// Number of writes issued, lets cached completions notice the data changed
+    pub writes: Cell<u64>,
+    /// Number of keys put so far, for the summary of a script
+    pub keys_written: Cell<u64>,
+    /// TTL the database was opened with
+    pub ttl: Option<Duration>,
+    /// Whether the database was opened read-only
+    pub readonly: bool,
+    /// Number of operations bulk writes put in each `WriteBatch`
+    pub batch_size: usize,
+    /// Length of the fixed prefix extractor, `None` when the database is opened without one
+    pub prefix_len: Option<usize>,
+    /// Read-only handle of a writable TTL database, with the `writes` count it was opened at
+    stored_values_db: RefCell<Option<(u64, DB)>>,
 }
 
-impl DBHelper {
-    pub fn new(path: &str, readonly: Option<bool>) -> Self {
-        let mut db_opts = Options::default();
-        let prefix_extractor = SliceTransform::create_fixed_prefix(4);
-        db_opts.set_prefix_extractor(prefix_extractor);
-        let cf_list = match DB::list_cf(&db_opts, path) {
-            Ok(cfs) => cfs,
-            Err(e) => {
-                eprintln!("Error listing column families: {}", e);
-                std::process::exit(1);
+pub const DEFAULT_BATCH_SIZE: usize = 1000;
+pub const DEFAULT_PREFIX_LEN: usize = 4;
+
+/// Per column family properties shown by `stats`, with their labels
+const CF_STAT_PROPERTIES: [(&str, &str); 11] = [
+    ("Estimated Keys", "rocksdb.estimate-num-keys"),
+    ("Live Data Size", "rocksdb.estimate-live-data-size"),
+    ("Total SST Size", "rocksdb.total-sst-files-size"),
+    ("L0 Files", "rocksdb.num-files-at-level0"),
+    ("Memtable Size", "rocksdb.cur-size-all-mem-tables"),
+    ("Immutable Memtables", "rocksdb.num-immutable-mem-table"),
+    ("Memtable Flush Pending", "rocksdb.mem-table-flush-pending"),
+    ("Compaction Pending", "rocksdb.compaction-pending"),
+    ("Running Compactions", "rocksdb.num-running-compactions"),
+    (
+        "Pending Compaction Bytes",
+        "rocksdb.estimate-pending-compaction-bytes",
+    ),
+    ("Block Cache Usage", "rocksdb.block-cache-usage"),
+];
+
+/// Write options for the `--sync` and `--disable-wal` flags of mutating commands
+pub fn write_options(sync: bool, disable_wal: bool) -> WriteOptions {
+    let mut options = WriteOptions::default();
+    options.set_sync(sync);
+    options.disable_wal(disable_wal);
+    options
+}
+
+/// Accumulates writes in a `WriteBatch`, committing it every `batch_size` operations
+/// so bulk writes don't build one huge batch in memory
+pub struct BatchWriter<'a> {
+    db: &'a DB,
+    /// `DBHelper::writes`, bumped on every commit
+    writes: &'a Cell<u64>,
+    batch: WriteBatch,
+    batch_size: usize,
+    write_options: WriteOptions,
+    /// Number of operations committed so far
+    pub committed: usize,
+}
+
+impl BatchWriter<'_> {
+    pub fn put_cf(
+        &mut self,
+        cf: &rocksdb::ColumnFamily,
+        key: impl AsRef<[u8]>,
+        value: impl AsRef<[u8]>,
+    ) -> Result<()> {
+        self.batch.put_cf(cf, key, value);
+        self.commit_if_full()
+    }
+
+    pub fn delete_cf(&mut self, cf: &rocksdb::ColumnFamily, key: impl AsRef<[u8]>) -> Result<()> {
+        self.batch.delete_cf(cf, key);
+        self.commit_if_full()
+    }
+
+    fn commit_if_full(&mut self) -> Result<()> {
+        if self.batch.len() >= self.batch_size {
+            self.commit()?;
+            eprint!("\rCommitted {} operations", self.committed);
+        }
+        Ok(())
+    }
+
+    fn commit(&mut self) -> Result<()> {
+        let batch = std::mem::take(&mut self.batch);
+        let len = batch.len();
+        self.db.write_opt(batch, &self.write_options)?;
+        self.writes.set(self.writes.get() + 1);
+        self.committed += len;
+        Ok(())
+    }
+
+    /// Commits the remaining operations and returns the total number committed
+    pub fn finish(mut self) -> Result<usize> {
+        let show_progress = self.committed > 0;
+        if !self.batch.is_empty() {
+            self.commit()?;
+        }
+        if show_progress {
+            eprintln!("\rCommitted {} operations", self.committed);
+        }
+        Ok(self.committed)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ScanStats {
+    /// Iterator read errors met by the scan
+    pub errors: Cell<usize>,
+    /// Entries read after the scan was resumed from the last good key, lost without retrying
+    pub recovered: Cell<usize>,
+    /// Entries that failed to read once no retries were left, the scan stops at them
+    pub skipped: Cell<usize>,
+}
+
+impl ScanStats {
+    fn reset(&self) {
+        self.errors.set(0);
+        self.recovered.set(0);
+        self.skipped.set(0);
+    }
+
+    fn add(&self, other: &ScanStats) {
+        self.errors.set(self.errors.get() + other.errors.get());
+        self.recovered
+            .set(self.recovered.get() + other.recovered.get());
+        self.skipped.set(self.skipped.get() + other.skipped.get());
+    }
+}
+
+/// Perf context counters summed over the point lookups of `read-amp`
+#[derive(Debug, Default)]
+pub struct ReadAmpStats {
+    pub keys: usize,
+    pub found: usize,
+    pub block_reads: u64,
+    pub block_read_bytes: u64,
+    pub block_cache_hits: u64,
+    pub memtable_gets: u64,
+    /// Lookups the SST bloom filters let through
+    pub bloom_sst_hits: u64,
+    /// Lookups the SST bloom filters answered without reading the file
+    pub bloom_sst_misses: u64,
+}
+
+/// A difference between the current column family and another one
+#[derive(Debug)]
+pub enum DiffEntry {
+    /// Key only exists in the current column family
+    Removed(Box<[u8]>, Box<[u8]>),
+    /// Key only exists in the other column family
+    Added(Box<[u8]>, Box<[u8]>),
+    /// Key exists in both, with the current and the other value
+    Changed(Box<[u8]>, Box<[u8]>, Box<[u8]>),
+}
+
+/// Where a timestamp is embedded in the keys of a column family
+#[derive(Debug, Clone, Copy)]
+pub struct KeyTimestampLayout {
+    /// Byte offset of the timestamp in the key
+    pub offset: usize,
+    /// Width of the timestamp in bytes, 4 or 8
+    pub width: usize,
+    pub little_endian: bool,
+    /// Whether the timestamp counts milliseconds instead of seconds
+    pub millis: bool,
+}
+
+/// A key with the timestamp decoded from it, `None` if the key doesn't match the layout
+pub type KeyTimestamp = (Box<[u8]>, Option<SystemTime>);
+
+/// A key and its value as read from RocksDB
+pub type KeyValue = (Box<[u8]>, Box<[u8]>);
+
+/// A looked up key and its value, `None` if the key doesn't exist
+pub type KeyLookup = (Vec<u8>, Option<Vec<u8>>);
+
+impl KeyTimestampLayout {
+    /// Layout of the write time RocksDB's TTL mode appends to every value, 4 bytes of
+    /// little-endian seconds at the end
+    pub fn ttl_suffix(value: &[u8]) -> Self {
+        KeyTimestampLayout {
+            offset: value.len().saturating_sub(4),
+            width: 4,
+            little_endian: true,
+            millis: false,
+        }
+    }
+
+    pub fn decode(&self, key: &[u8]) -> Option<SystemTime> {
+        let bytes = key.get(self.offset..self.offset.checked_add(self.width)?)?;
+        let value = match (self.width, self.little_endian) {
+            (4, false) => u32::from_be_bytes(bytes.try_into().ok()?) as u64,
+            (4, true) => u32::from_le_bytes(bytes.try_into().ok()?) as u64,
+            (8, false) => u64::from_be_bytes(bytes.try_into().ok()?),
+            (8, true) => u64::from_le_bytes(bytes.try_into().ok()?),
+            _ => return None,
+        };
+        let since_epoch = if self.millis {
+            Duration::from_millis(value)
+        } else {
+            Duration::from_secs(value)
+        };
+        UNIX_EPOCH.checked_add(since_epoch)
+    }
+}
+
+/// Which part of an entry `search_matches` looks for the pattern in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum SearchTarget {
+    #[value(name = "keys")]
+    Key,
+    #[value(name = "values")]
+    Value,
+}
+
+/// Entries counted by `count_cf`
+#[derive(Debug)]
+pub enum CountMode {
+    All,
+    Prefix(String),
+    Range {
+        start: Option<String>,
+        end: Option<String>,
+    },
+    Pattern {
+        pattern: SearchPattern,
+        target: SearchTarget,
+    },
+}
+
+/// A search pattern, compiled once before the scan when it is a regex
+#[derive(Debug)]
+pub enum SearchPattern {
+    Literal(String),
+    Regex(Regex),
+}
+
+impl SearchPattern {
+    pub fn new(pattern: &str, regex: bool) -> Result<Self> {
+        if regex {
+            Ok(SearchPattern::Regex(Regex::new(pattern)?))
+        } else {
+            Ok(SearchPattern::Literal(pattern.to_string()))
+        }
+    }
+
+    fn is_match(&self, haystack: &[u8]) -> bool {
+        match self {
+            // `windows(0)` panics, and an empty pattern is found everywhere
+            SearchPattern::Literal(pattern) if pattern.is_empty() => true,
+            SearchPattern::Literal(pattern) => haystack
+                .windows(pattern.len())
+                .any(|window| window == pattern.as_bytes()),
+            SearchPattern::Regex(regex) => regex.is_match(&String::from_utf8_lossy(haystack)),
+        }
+    }
+
+    fn highlight(&self, candidates: Vec<u8>) -> Vec<u8> {
+        match self {
+            SearchPattern::Literal(pattern) => highlight_pattern(pattern, candidates),
+            SearchPattern::Regex(regex) => highlight_regex(regex, candidates),
+        }
+    }
+}
+
+/// Entries of `iter` whose key or value matches `pattern`, with the matches highlighted in the
+/// value if `highlight_matched` is set
+pub fn search_matches(
+    iter: RetryIter<'_>,
+    pattern: &SearchPattern,
+    target: SearchTarget,
+    highlight_matched: bool,
+) -> impl Iterator<Item = (Vec<u8>, Vec<u8>)> {
+    iter.filter(move |(key, value)| {
+        let haystack = match target {
+            SearchTarget::Key => key,
+            SearchTarget::Value => value,
+        };
+        pattern.is_match(haystack)
+    })
+    .map(move |(key, value)| {
+        if highlight_matched {
+            let highlighted_value = pattern.highlight(value.into_vec());
+            (key.to_vec(), highlighted_value)
+        } else {
+            (key.to_vec(), value.to_vec())
+        }
+    })
+}
+
+/// SST files of a column family, as reported by RocksDB
+#[derive(Debug, Default)]
+pub struct SstSummary {
+    pub level0_files: u64,
+    pub total_size: u64,
+}
+
+/// Bounds of an iteration, kept around so the iterator can be recreated
+#[derive(Debug, Default)]
+struct IterBounds {
+    lower: Option<Vec<u8>>,
+    upper: Option<Vec<u8>>,
+    prefix: Option<Vec<u8>>,
+    /// Length of the database's prefix extractor
+    extractor_len: Option<usize>,
+}
+
+impl IterBounds {
+    fn read_options(&self, reverse: bool) -> ReadOptions {
+        let mut options = ReadOptions::default();
+        if let Some(lower) = &self.lower {
+            options.set_iterate_lower_bound(lower.clone());
+        }
+        if let Some(upper) = &self.upper {
+            options.set_iterate_upper_bound(upper.clone());
+        }
+        if let Some(prefix) = &self.prefix {
+            // bound the iterator to [prefix, successor) so it stays within the prefix whatever
+            // the extractor is, seeking backwards then starts from the end of that range
+            options.set_iterate_lower_bound(prefix.clone());
+            if let Some(upper) = prefix_successor(prefix) {
+                options.set_iterate_upper_bound(upper);
+            }
+            // the prefix bloom filter only answers for keys of exactly the extractor's length
+            if reverse || self.extractor_len != Some(prefix.len()) {
+                options.set_total_order_seek(true);
+            } else {
+                options.set_prefix_same_as_start(true);
             }
+        }
+        options
+    }
+}
+
+/// Smallest key greater than every key starting with `prefix`, `None` if there is none
+fn prefix_successor(prefix: &[u8]) -> Option<Vec<u8>> {
+    let mut successor = prefix.to_vec();
+    while let Some(last) = successor.pop() {
+        if last < u8::MAX {
+            successor.push(last + 1);
+            return Some(successor);
+        }
+    }
+    None
+}
+
+/// Iterates a column family, recreating the RocksDB iterator from the last
+/// good key when a read error occurs, at most `retries_left` times.
+pub struct RetryIter<'a> {
+    db: &'a DB,
+    cf: &'a rocksdb::ColumnFamily,
+    bounds: IterBounds,
+    reverse: bool,
+    iter: DBIteratorWithThreadMode<'a, DB>,
+    last_key: Option<Box<[u8]>>,
+    /// The iterator was just recreated and may start at `last_key`
+    resumed: bool,
+    /// The scan was resumed at least once, later entries count as recovered
+    recovering: bool,
+    retries_left: usize,
+    stats: &'a ScanStats,
+}
+
+impl<'a> RetryIter<'a> {
+    fn new(
+        db: &'a DB,
+        cf: &'a rocksdb::ColumnFamily,
+        bounds: IterBounds,
+        reverse: bool,
+        retry: usize,
+        stats: &'a ScanStats,
+    ) -> Self {
+        stats.reset();
+        let iter = Self::open(db, cf, &bounds, reverse, None);
+        RetryIter {
+            db,
+            cf,
+            bounds,
+            reverse,
+            iter,
+            last_key: None,
+            resumed: false,
+            recovering: false,
+            retries_left: retry,
+            stats,
+        }
+    }
+
+    fn open(
+        db: &'a DB,
+        cf: &'a rocksdb::ColumnFamily,
+        bounds: &IterBounds,
+        reverse: bool,
+        from: Option<&[u8]>,
+    ) -> DBIteratorWithThreadMode<'a, DB> {
+        let direction = if reverse {
+            Direction::Reverse
+        } else {
+            Direction::Forward
         };
+        let mode = match (from, &bounds.prefix) {
+            (Some(key), _) => IteratorMode::From(key, direction),
+            (None, Some(_)) if reverse => IteratorMode::End,
+            (None, Some(prefix)) => IteratorMode::From(prefix, direction),
+            (None, None) if reverse => IteratorMode::End,
+            (None, None) => IteratorMode::Start,
+        };
+        db.iterator_cf_opt(cf, bounds.read_options(reverse), mode)
+    }
+}
+
+impl Iterator for RetryIter<'_> {
+    type Item = (Box<[u8]>, Box<[u8]>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.iter.next() {
+                Some(Ok((key, value))) => {
+                    if self.resumed {
+                        self.resumed = false;
+                        // the recreated iterator starts at the last key we already returned
+                        if self.last_key.as_ref() == Some(&key) {
+                            continue;
+                        }
+                    }
+                    if self.recovering {
+                        self.stats.recovered.set(self.stats.recovered.get() + 1);
+                    }
+                    if self.retries_left > 0 {
+                        self.last_key = Some(key.clone());
+                    }
+                    return Some((key, value));
+                }
+                Some(Err(e)) => {
+                    self.stats.errors.set(self.stats.errors.get() + 1);
+                    if self.retries_left == 0 {
+                        eprintln!("Error occurred while iterating: {}", e);
+                        self.stats.skipped.set(self.stats.skipped.get() + 1);
+                        return None;
+                    }
+                    self.retries_left -= 1;
+                    self.resumed = true;
+                    self.recovering = true;
+                    self.iter = Self::open(
+                        self.db,
+                        self.cf,
+                        &self.bounds,
+                        self.reverse,
+                        self.last_key.as_deref(),
+                    );
+                }
+                None => return None,
+            }
+        }
+    }
+}
+
+/// Options of a column family, with a fixed prefix extractor of `prefix_len` bytes if set
+fn cf_options(prefix_len: Option<usize>) -> Options {
+    let mut cf_opts = Options::default();
+    if let Some(prefix_len) = prefix_len {
+        cf_opts.set_prefix_extractor(SliceTransform::create_fixed_prefix(prefix_len));
+    }
+    cf_opts
+}
+
+/// Descriptors opening each of `cf_list`, including `default`, with the options of
+/// `cf_options`. The prefix extractor is a column family option, setting it on the database
+/// options has no effect on the column families
+fn cf_descriptors(cf_list: &[String], prefix_len: Option<usize>) -> Vec<ColumnFamilyDescriptor> {
+    cf_list
+        .iter()
+        .map(|name| ColumnFamilyDescriptor::new(name, cf_options(prefix_len)))
+        .collect()
+}
+
+impl DBHelper {
+    /// Opens the database at `path`, with a fixed prefix extractor of `prefix_len` bytes if set
+    pub fn new(
+        path: &str,
+        readonly: Option<bool>,
+        ttl: Option<Duration>,
+        prefix_len: Option<usize>,
+    ) -> Result<Self> {
+        let mut db_opts = Options::default();
+        let cf_list = DB::list_cf(&db_opts, path).map_err(|source| CliError::DbOpen {
+            path: path.to_string(),
+            source,
+        })?;
         println!("{:?}", cf_list);
-        let db;
-        if readonly.is_some() && readonly.unwrap() {
-            db = DBHelper::new_readonly_db(path, db_opts, &cf_list);
+        let readonly = readonly == Some(true);
+        let cfs = cf_descriptors(&cf_list, prefix_len);
+        let db = if readonly {
+            DBHelper::new_readonly_db(path, db_opts, cfs)
+        } else if let Some(ttl) = ttl {
+            DBHelper::new_ttl_db(path, &mut db_opts, cfs, ttl)
         } else {
-            db = DBHelper::new_writable_db(path, &mut db_opts, &cf_list);
+            DBHelper::new_writable_db(path, &mut db_opts, cfs)
         }
-        DBHelper {
+        .map_err(|source| CliError::DbOpen {
+            path: path.to_string(),
+            source,
+        })?;
+        Ok(DBHelper {
             db,
             path: path.to_string(),
             current_cf: if cf_list.is_empty() {
@@ -43,17 +544,44 @@ impl DBHelper {
                 cf_list[0].clone()
             },
             cf_list,
-        }
+            scan_stats: ScanStats::default(),
+            writes: Cell::new(0),
+            keys_written: Cell::new(0),
+            ttl,
+            readonly,
+            batch_size: DEFAULT_BATCH_SIZE,
+            prefix_len,
+            stored_values_db: RefCell::new(None),
+        })
     }
 
-    fn new_readonly_db(path: &str, db_opts: Options, cf_list: &Vec<String>) -> rocksdb::DB {
-        DB::open_cf_for_read_only(&db_opts, path, cf_list, false).unwrap()
+    fn new_readonly_db(
+        path: &str,
+        db_opts: Options,
+        cfs: Vec<ColumnFamilyDescriptor>,
+    ) -> std::result::Result<DB, rocksdb::Error> {
+        DB::open_cf_descriptors_read_only(&db_opts, path, cfs, false)
+    }
+
+    fn new_writable_db(
+        path: &str,
+        db_opts: &mut Options,
+        cfs: Vec<ColumnFamilyDescriptor>,
+    ) -> std::result::Result<DB, rocksdb::Error> {
+        db_opts.create_if_missing(true);
+        db_opts.create_missing_column_families(true);
+        DB::open_cf_descriptors(db_opts, path, cfs)
     }
 
-    fn new_writable_db(path: &str, db_opts: &mut Options, cf_list: &Vec<String>) -> rocksdb::DB {
+    fn new_ttl_db(
+        path: &str,
+        db_opts: &mut Options,
+        cfs: Vec<ColumnFamilyDescriptor>,
+        ttl: Duration,
+    ) -> std::result::Result<DB, rocksdb::Error> {
         db_opts.create_if_missing(true);
         db_opts.create_missing_column_families(true);
-        DB::open_cf(&db_opts, path, cf_list.iter()).unwrap()
+        DB::open_cf_descriptors_with_ttl(db_opts, path, cfs, ttl)
     }
 
     pub fn get_cfs_names(&self) -> Vec<String> {
@@ -64,66 +592,615 @@ impl DBHelper {
         self.db.cf_handle(name)
     }
 
-    pub fn get(&self, key: &str, as_json: bool) -> Result<()> {
-        let cf = self.get_cf_handle(&self.current_cf).unwrap();
+    fn current_cf_handle(&self) -> Result<&rocksdb::ColumnFamily> {
+        self.get_cf_handle(&self.current_cf)
+            .ok_or_else(|| CliError::CfNotFound(self.current_cf.clone()))
+    }
+
+    pub fn create_cf(&mut self, name: &str) -> Result<()> {
+        if self.readonly {
+            return Err(CliError::ReadOnly);
+        }
+        if self.cf_list.iter().any(|cf| cf == name) {
+            return Err(CliError::CfExists(name.to_string()));
+        }
+        self.db.create_cf(name, &cf_options(self.prefix_len))?;
+        self.cf_list.push(name.to_string());
+        self.record_write();
+        Ok(())
+    }
+
+    pub fn drop_cf(&mut self, name: &str) -> Result<()> {
+        if self.readonly {
+            return Err(CliError::ReadOnly);
+        }
+        if name == "default" {
+            return Err(CliError::DropDefaultCf);
+        }
+        if !self.cf_list.iter().any(|cf| cf == name) {
+            return Err(CliError::CfNotFound(name.to_string()));
+        }
+        self.db.drop_cf(name)?;
+        self.cf_list.retain(|cf| cf != name);
+        self.record_write();
+        Ok(())
+    }
+
+    /// Copies every entry of the column family `src` into `dst`, creating `dst` if it doesn't
+    /// exist, in batches of `batch_size`. Keys already in `dst` are kept unless `overwrite` is
+    /// set. Returns the number of entries copied and the number skipped because they exist
+    pub fn copy_cf(
+        &mut self,
+        src: &str,
+        dst: &str,
+        overwrite: bool,
+        batch_size: usize,
+        write_options: WriteOptions,
+    ) -> Result<(usize, usize)> {
+        if self.readonly {
+            return Err(CliError::ReadOnly);
+        }
+        if src == dst {
+            return Err(CliError::CopyToSelf(src.to_string()));
+        }
+        // checked before dst is created, so a mistyped source leaves no empty column family
+        if !self.cf_list.iter().any(|cf| cf == src) {
+            return Err(CliError::CfNotFound(src.to_string()));
+        }
+        if !self.cf_list.iter().any(|cf| cf == dst) {
+            self.create_cf(dst)?;
+        }
+        let src_cf = self
+            .get_cf_handle(src)
+            .ok_or_else(|| CliError::CfNotFound(src.to_string()))?;
+        let dst_cf = self
+            .get_cf_handle(dst)
+            .ok_or_else(|| CliError::CfNotFound(dst.to_string()))?;
+        let mut writer = BatchWriter {
+            batch_size: batch_size.max(1),
+            ..self.batch_writer(write_options)
+        };
+        let mut skipped = 0;
+        for item in self.db.iterator_cf(src_cf, IteratorMode::Start) {
+            let (key, value) = item?;
+            if !overwrite && self.db.get_pinned_cf(dst_cf, &key)?.is_some() {
+                skipped += 1;
+                continue;
+            }
+            writer.put_cf(dst_cf, key, value)?;
+        }
+        let copied = writer.finish()?;
+        self.record_keys_written(copied);
+        Ok((copied, skipped))
+    }
+
+    /// Writer for bulk operations, committing every `self.batch_size` operations
+    pub fn batch_writer(&self, write_options: WriteOptions) -> BatchWriter<'_> {
+        BatchWriter {
+            db: &self.db,
+            writes: &self.writes,
+            batch: WriteBatch::default(),
+            batch_size: self.batch_size.max(1),
+            write_options,
+            committed: 0,
+        }
+    }
+
+    fn record_write(&self) {
+        self.writes.set(self.writes.get() + 1);
+    }
+
+    fn record_keys_written(&self, count: usize) {
+        self.keys_written
+            .set(self.keys_written.get() + count as u64);
+    }
+
+    /// Fails if the last scan stopped at an entry it couldn't read, after `read` entries, so
+    /// a partial count or export isn't reported as a success
+    fn check_scan_complete(&self, read: usize) -> Result<()> {
+        if self.scan_stats.skipped.get() > 0 {
+            return Err(CliError::IncompleteScan(read));
+        }
+        Ok(())
+    }
+
+    fn iter_current_cf(
+        &self,
+        bounds: IterBounds,
+        reverse: bool,
+        retry: usize,
+    ) -> Result<RetryIter<'_>> {
+        let cf = self.current_cf_handle()?;
+        let bounds = IterBounds {
+            extractor_len: self.prefix_len,
+            ..bounds
+        };
+        Ok(RetryIter::new(
+            &self.db,
+            cf,
+            bounds,
+            reverse,
+            retry,
+            &self.scan_stats,
+        ))
+    }
+
+    pub fn get(
+        &self,
+        key: &[u8],
+        as_json: bool,
+        show_seq: bool,
+        options: DisplayOptions,
+    ) -> Result<()> {
+        let cf = self.current_cf_handle()?;
         match self.db.get_cf(cf, key)? {
             Some(value) => {
                 if as_json {
                     let value_str = String::from_utf8_lossy(&value);
                     match serde_json::from_str::<String>(&value_str) {
-                        Ok(json_val) => print_key_value(key.as_bytes(), json_val.as_bytes()),
+                        Ok(json_val) => print_key_value(key, json_val.as_bytes(), options),
                         Err(_) => println!("{}", value_str),
                     }
                 } else {
-                    print_key_value(key.as_bytes(), &value);
+                    print_key_value(key, &value, options);
                 }
             }
-            None => println!("Key not found"),
+            None => print_missing_key(key, options),
+        }
+        if show_seq {
+            self.print_key_sequence(key)?;
         }
         Ok(())
     }
 
-    pub fn get_keys(&self, limit: usize) -> Result<Vec<String>> {
-        let cf = self.get_cf_handle(&self.current_cf).unwrap();
-        let mut keys = Vec::with_capacity(limit);
-        let mut iter = self.db.iterator_cf(cf, rocksdb::IteratorMode::Start);
-        while let Some(key_values) = iter.next() {
-            match key_values {
-                Ok((key, _)) => {
-                    keys.push(String::from_utf8_lossy(&key).to_string());
-                    if keys.len() >= limit {
-                        break;
-                    }
+    /// Column families named in a comma-separated `--only-cf` list, or all of them if `None`
+    pub fn select_cfs(&self, only: Option<&str>) -> Result<Vec<String>> {
+        let Some(only) = only else {
+            return Ok(self.cf_list.clone());
+        };
+        only.split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(|name| {
+                if self.cf_list.iter().any(|cf| cf == name) {
+                    Ok(name.to_string())
+                } else {
+                    Err(CliError::CfNotFound(name.to_string()))
                 }
-                Err(_) => {
-                    println!("Error occurred while iterating over keys");
+            })
+            .collect()
+    }
+
+    /// Value of `key` in each of `cfs`, `None` where the key doesn't exist
+    pub fn get_all_cf(&self, key: &[u8], cfs: &[String]) -> Result<Vec<(String, Option<Vec<u8>>)>> {
+        cfs.iter()
+            .map(|name| {
+                let cf = self
+                    .get_cf_handle(name)
+                    .ok_or_else(|| CliError::CfNotFound(name.clone()))?;
+                Ok((name.clone(), self.db.get_cf(cf, key)?))
+            })
+            .collect()
+    }
+
+    /// Values of `keys` in the current column family, read in one batched lookup. `None` where
+    /// the key doesn't exist
+    pub fn multi_get(&self, keys: Vec<Vec<u8>>) -> Result<Vec<KeyLookup>> {
+        let cf = self.current_cf_handle()?;
+        let values = self.db.multi_get_cf(keys.iter().map(|key| (cf, key)));
+        keys.into_iter()
+            .zip(values)
+            .map(|(key, value)| Ok((key, value?)))
+            .collect()
+    }
+
+    /// Value of `key` as stored, including the write time appended in TTL mode. A writable TTL
+    /// database strips it on read, so the value is read through a separate read-only handle.
+    /// The handle is kept until the next write, which it wouldn't see.
+    pub fn get_stored_value(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        if self.readonly || self.ttl.is_none() {
+            return self.get_value(key);
+        }
+        let mut stored_values_db = self.stored_values_db.borrow_mut();
+        let writes = self.writes.get();
+        let db = match stored_values_db.take() {
+            Some((opened_at, db)) if opened_at == writes => db,
+            _ => DB::open_cf_descriptors_read_only(
+                &Options::default(),
+                &self.path,
+                cf_descriptors(&self.cf_list, self.prefix_len),
+                false,
+            )
+            .map_err(|source| CliError::DbOpen {
+                path: self.path.clone(),
+                source,
+            })?,
+        };
+        let value = match db.cf_handle(&self.current_cf) {
+            Some(cf) => db.get_cf(cf, key)?,
+            None => return Err(CliError::CfNotFound(self.current_cf.clone())),
+        };
+        *stored_values_db = Some((writes, db));
+        Ok(value)
+    }
+
+    pub fn get_value(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        let cf = self.current_cf_handle()?;
+        Ok(self.db.get_cf(cf, key)?)
+    }
+
+    /// Existence check without IO, the value is only shown when RocksDB has it in memory
+    pub fn probe(&self, key: &[u8], options: DisplayOptions) -> Result<()> {
+        let cf = self.current_cf_handle()?;
+        match self
+            .db
+            .key_may_exist_cf_opt_value(cf, key, &ReadOptions::default())
+        {
+            (false, _) => println!("Key not found"),
+            (true, Some(value)) => print_key_value(key, &value, options),
+            (true, None) => println!(
+                "Key {} may exist, value not cached",
+                String::from_utf8_lossy(key).bright_green()
+            ),
+        }
+        Ok(())
+    }
+
+    /// Sequence numbers of the latest write to `key` that is still in the WAL, a single one
+    /// unless the batch has operations the WAL decoder skipped, then those of the whole batch
+    pub fn key_sequence(&self, key: &[u8]) -> Result<Option<RangeInclusive<u64>>> {
+        let mut latest = None;
+        for batch in read_wal(&self.db, 0)? {
+            let batch = batch?;
+            for op in &batch.ops {
+                if *op.key == *key {
+                    latest = Some(match op.seq {
+                        Some(seq) => seq..=seq,
+                        None => batch.seq..=batch.last_seq(),
+                    });
                 }
             }
         }
+        Ok(latest)
+    }
+
+    fn print_key_sequence(&self, key: &[u8]) -> Result<()> {
+        if self.current_cf != "default" {
+            println!(
+                "Sequence numbers are only available for the {} column family",
+                "default".bright_green()
+            );
+            return Ok(());
+        }
+        match self.key_sequence(key)? {
+            Some(seqs) if seqs.start() == seqs.end() => println!(
+                "Sequence number: {}",
+                seqs.start().to_string().bright_green()
+            ),
+            Some(seqs) => println!(
+                "Sequence number: between {} and {}, the batch has operations the WAL decoder skips",
+                seqs.start().to_string().bright_green(),
+                seqs.end().to_string().bright_green()
+            ),
+            None => println!("Sequence number: not found in the WAL (already flushed?)"),
+        }
+        Ok(())
+    }
+
+    pub fn get_keys(&self, limit: usize, reverse: bool, retry: usize) -> Result<Vec<Box<[u8]>>> {
+        let keys = self
+            .iter_current_cf(IterBounds::default(), reverse, retry)?
+            .take(limit)
+            .map(|(key, _)| key)
+            .collect();
+        Ok(keys)
+    }
+
+    /// Keys of the current column family starting with `prefix`, in key order.
+    /// Uses a plain iterator rather than `RetryIter`, so completing a key doesn't reset the
+    /// `scan_stats` of the last command
+    pub fn keys_with_prefix(&self, prefix: &str, limit: usize) -> Result<Vec<String>> {
+        let cf = self.current_cf_handle()?;
+        let bounds = IterBounds {
+            lower: Some(prefix.as_bytes().to_vec()),
+            ..Default::default()
+        };
+        let mode = IteratorMode::From(prefix.as_bytes(), Direction::Forward);
+        let keys = self
+            .db
+            .iterator_cf_opt(cf, bounds.read_options(false), mode)
+            .map_while(|item| item.ok())
+            .take_while(|(key, _)| key.starts_with(prefix.as_bytes()))
+            .take(limit)
+            .map(|(key, _)| String::from_utf8_lossy(&key).to_string())
+            .collect();
         Ok(keys)
     }
 
-    pub fn put(&self, key: &str, value: &str) -> Result<()> {
-        let cf = self.get_cf_handle(&self.current_cf).unwrap();
-        self.db.put_cf(cf, key, value)?;
+    /// Looks up the first `sample` keys of the current column family one by one and sums the
+    /// perf context counters of the lookups. The keys are collected without filling the block
+    /// cache, so the lookups aren't all served from blocks the scan just loaded.
+    pub fn read_amp(&self, sample: usize) -> Result<ReadAmpStats> {
+        let cf = self.current_cf_handle()?;
+        let mut scan_options = ReadOptions::default();
+        scan_options.fill_cache(false);
+        let keys = self
+            .db
+            .iterator_cf_opt(cf, scan_options, IteratorMode::Start)
+            .take(sample)
+            .map(|item| item.map(|(key, _)| key))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        set_perf_stats(PerfStatsLevel::EnableCount);
+        let mut context = PerfContext::default();
+        context.reset();
+        let mut found = 0;
+        let lookups = keys.iter().try_for_each(|key| {
+            if self.db.get_cf(cf, key)?.is_some() {
+                found += 1;
+            }
+            Ok::<_, rocksdb::Error>(())
+        });
+        let stats = ReadAmpStats {
+            keys: keys.len(),
+            found,
+            block_reads: context.metric(PerfMetric::BlockReadCount),
+            block_read_bytes: context.metric(PerfMetric::BlockReadByte),
+            block_cache_hits: context.metric(PerfMetric::BlockCacheHitCount),
+            memtable_gets: context.metric(PerfMetric::GetFromMemtableCount),
+            bloom_sst_hits: context.metric(PerfMetric::BloomSstHitCount),
+            bloom_sst_misses: context.metric(PerfMetric::BloomSstMissCount),
+        };
+        set_perf_stats(PerfStatsLevel::Disable);
+        lookups?;
+        Ok(stats)
+    }
+
+    /// The first `sample` keys of the current column family with the timestamp decoded from each
+    pub fn key_timestamps(
+        &self,
+        layout: KeyTimestampLayout,
+        sample: usize,
+    ) -> Result<Vec<KeyTimestamp>> {
+        let entries = self
+            .iter_current_cf(IterBounds::default(), false, 0)?
+            .take(sample)
+            .map(|(key, _)| {
+                let timestamp = layout.decode(&key);
+                (key, timestamp)
+            })
+            .collect();
+        Ok(entries)
+    }
+
+    /// Entry at position `index` of the current column family, found by iterating from the start
+    pub fn nth(&self, index: usize) -> Result<Option<KeyValue>> {
+        Ok(self
+            .iter_current_cf(IterBounds::default(), false, 0)?
+            .nth(index))
+    }
+
+    /// Bytes of a key or value argument, hex decoded if `hex` is set
+    pub fn decode_key(raw: &str, hex: bool) -> Result<Vec<u8>> {
+        if hex {
+            hex::decode(raw).map_err(|e| CliError::KeyDecode(format!("{}: {}", raw, e)))
+        } else {
+            Ok(raw.as_bytes().to_vec())
+        }
+    }
+
+    pub fn put(&self, key: &[u8], value: &[u8], write_options: &WriteOptions) -> Result<()> {
+        let cf = self.current_cf_handle()?;
+        self.db.put_cf_opt(cf, key, value, write_options)?;
+        self.record_write();
+        self.record_keys_written(1);
         println!(
             "Successfully put {} {}",
-            key.bright_green(),
-            value.bright_green()
+            String::from_utf8_lossy(key).bright_green(),
+            String::from_utf8_lossy(value).bright_green()
         );
         Ok(())
     }
 
+    /// Writes all `pairs` to the current column family in one atomic `WriteBatch`
+    pub fn put_batch(
+        &self,
+        pairs: Vec<(&str, &str)>,
+        write_options: &WriteOptions,
+    ) -> Result<usize> {
+        if self.readonly {
+            return Err(CliError::ReadOnly);
+        }
+        let cf = self.current_cf_handle()?;
+        let mut batch = WriteBatch::default();
+        for (key, value) in &pairs {
+            batch.put_cf(cf, key, value);
+        }
+        self.db.write_opt(batch, write_options)?;
+        self.record_write();
+        self.record_keys_written(pairs.len());
+        Ok(pairs.len())
+    }
+
+    /// Deletes all `keys` from the current column family in one atomic `WriteBatch`
+    pub fn delete_batch(&self, keys: Vec<&str>, write_options: &WriteOptions) -> Result<usize> {
+        if self.readonly {
+            return Err(CliError::ReadOnly);
+        }
+        let cf = self.current_cf_handle()?;
+        let mut batch = WriteBatch::default();
+        for key in &keys {
+            batch.delete_cf(cf, key);
+        }
+        self.db.write_opt(batch, write_options)?;
+        self.record_write();
+        Ok(keys.len())
+    }
+
+    /// Deletes the keys from `start` (inclusive) to `end` (exclusive) of the current column family
+    /// with one range tombstone, then compacts the range so the tombstones do not slow down later
+    /// reads. The keys aren't counted, so the delete costs the same whatever the size of the range
+    pub fn delete_range(
+        &self,
+        start: &[u8],
+        end: &[u8],
+        write_options: &WriteOptions,
+    ) -> Result<()> {
+        if self.readonly {
+            return Err(CliError::ReadOnly);
+        }
+        let cf = self.current_cf_handle()?;
+        self.db.delete_range_cf_opt(cf, start, end, write_options)?;
+        self.record_write();
+        self.db.compact_range_cf(cf, Some(start), Some(end));
+        Ok(())
+    }
+
+    /// Labelled RocksDB properties of the column family `cf_name`, ending with the compaction
+    /// and stall report of `rocksdb.cfstats-no-file-histogram`. Properties RocksDB doesn't
+    /// report are left out
+    pub fn cf_statistics(&self, cf_name: &str) -> Result<Vec<(String, String)>> {
+        let cf = self
+            .get_cf_handle(cf_name)
+            .ok_or_else(|| CliError::CfNotFound(cf_name.to_string()))?;
+        let mut stats = Vec::new();
+        for (label, property) in CF_STAT_PROPERTIES {
+            if let Some(value) = self.db.property_value_cf(cf, property)? {
+                stats.push((label.to_string(), value));
+            }
+        }
+        if let Some(report) = self
+            .db
+            .property_value_cf(cf, "rocksdb.cfstats-no-file-histogram")?
+        {
+            stats.push((
+                "Compaction Stats".to_string(),
+                report.trim_end().to_string(),
+            ));
+        }
+        Ok(stats)
+    }
+
+    fn sst_summary(&self, cf: &rocksdb::ColumnFamily) -> Result<SstSummary> {
+        let property = |name| self.db.property_int_value_cf(cf, name);
+        Ok(SstSummary {
+            level0_files: property("rocksdb.num-files-at-level0")?.unwrap_or(0),
+            total_size: property("rocksdb.total-sst-files-size")?.unwrap_or(0),
+        })
+    }
+
+    /// Compacts the keys from `start` (inclusive) to `end` (exclusive) of the column family
+    /// `cf_name`, unbounded ends extend to the first or last key. Blocks until RocksDB is done
+    /// and returns the SST files of the column family before and after the compaction
+    pub fn compact(
+        &self,
+        cf_name: &str,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+    ) -> Result<(SstSummary, SstSummary)> {
+        if self.readonly {
+            return Err(CliError::ReadOnly);
+        }
+        let cf = self
+            .get_cf_handle(cf_name)
+            .ok_or_else(|| CliError::CfNotFound(cf_name.to_string()))?;
+        let before = self.sst_summary(cf)?;
+        self.db.compact_range_cf(cf, start, end);
+        Ok((before, self.sst_summary(cf)?))
+    }
+
+    /// Counts the entries of the current column family selected by `mode`. Without `exact`,
+    /// `CountMode::All` returns RocksDB's `estimate-num-keys` instead of iterating; RocksDB has
+    /// no such estimate for part of a column family, so the other modes always iterate.
+    pub fn count_cf(&self, mode: CountMode, exact: bool) -> Result<u64> {
+        let (bounds, pattern) = match mode {
+            CountMode::All if !exact => {
+                let cf = self.current_cf_handle()?;
+                let estimate = self
+                    .db
+                    .property_int_value_cf(cf, "rocksdb.estimate-num-keys")?;
+                return Ok(estimate.unwrap_or(0));
+            }
+            CountMode::All => (IterBounds::default(), None),
+            CountMode::Prefix(prefix) => (
+                IterBounds {
+                    prefix: Some(prefix.into_bytes()),
+                    ..Default::default()
+                },
+                None,
+            ),
+            CountMode::Range { start, end } => (
+                IterBounds {
+                    lower: start.map(String::into_bytes),
+                    upper: end.map(String::into_bytes),
+                    ..Default::default()
+                },
+                None,
+            ),
+            CountMode::Pattern { pattern, target } => {
+                (IterBounds::default(), Some((pattern, target)))
+            }
+        };
+        let iter = self.iter_current_cf(bounds, false, 0)?;
+        let count = match pattern {
+            Some((pattern, target)) => search_matches(iter, &pattern, target, false).count(),
+            None => iter.count(),
+        };
+        self.check_scan_complete(count)?;
+        Ok(count as u64)
+    }
+
+    /// Writes every entry of the current column family to `writer`, one record at a time
+    pub fn export_cf(&self, writer: impl Write, format: ExportFormat) -> Result<usize> {
+        let mut records = RecordWriter::new(writer, format)?;
+        let mut exported = 0;
+        for (key, value) in self.iter_current_cf(IterBounds::default(), false, 0)? {
+            records.write(&key, &value)?;
+            exported += 1;
+        }
+        records.finish()?;
+        self.check_scan_complete(exported)?;
+        Ok(exported)
+    }
+
+    /// Puts the records read from `reader` into the current column family, committing them
+    /// in batches of `self.batch_size` as they are read
+    pub fn import_cf(
+        &self,
+        reader: impl BufRead,
+        format: ExportFormat,
+        write_options: WriteOptions,
+    ) -> Result<usize> {
+        if self.readonly {
+            return Err(CliError::ReadOnly);
+        }
+        let cf = self.current_cf_handle()?;
+        let mut writer = self.batch_writer(write_options);
+        for record in read_records(reader, format) {
+            let (key, value) = record?;
+            writer.put_cf(cf, key, value)?;
+        }
+        let imported = writer.finish()?;
+        self.record_keys_written(imported);
+        Ok(imported)
+    }
+
     pub fn prefix(
         &self,
-        prefix: &str,
+        prefix: &[u8],
         highlight_matched: bool,
+        reverse: bool,
+        retry: usize,
     ) -> Result<impl Iterator<Item = (Vec<u8>, Vec<u8>)>> {
-        let cf = self.get_cf_handle(&self.current_cf).unwrap();
-        let iter = self.db.prefix_iterator_cf(cf, prefix.as_bytes());
-        let key_values = iter.filter_map(|kv| kv.ok()).map(move |(key, value)| {
+        let bounds = IterBounds {
+            prefix: Some(prefix.to_vec()),
+            ..Default::default()
+        };
+        let iter = self.iter_current_cf(bounds, reverse, retry)?;
+        let key_values = iter.map(move |(key, value)| {
             if highlight_matched {
-                let highlighted_key: Vec<u8> = highlight_pattern(prefix, key.into_vec());
+                let highlighted_key: Vec<u8> =
+                    highlight_pattern(&String::from_utf8_lossy(prefix), key.into_vec());
                 (highlighted_key, value.into_vec())
             } else {
                 (key.into_vec(), value.into_vec())
@@ -132,7 +1209,7 @@ impl DBHelper {
         Ok(key_values)
     }
 
-    pub fn contains_stringkey(&self, key: &str) -> bool {
+    pub fn contains_key(&self, key: &[u8]) -> bool {
         if let Some(cf) = self.get_cf_handle(&self.current_cf) {
             if let Ok(result) = self.db.get_pinned_cf(cf, key) {
                 if let Some(_) = result { true } else { false }
@@ -146,92 +1223,193 @@ impl DBHelper {
 
     pub fn search_key(
         &self,
-        pattern: &str,
+        pattern: &SearchPattern,
         highlight_matched: bool,
+        reverse: bool,
+        retry: usize,
     ) -> Result<impl Iterator<Item = (Vec<u8>, Vec<u8>)>> {
-        let cf = self.get_cf_handle(&self.current_cf).unwrap();
-        let iter = self.db.iterator_cf(cf, IteratorMode::Start);
-        let results = iter
-            .filter_map(|item| item.ok())
-            .filter(|value| {
-                value
-                    .0
-                    .windows(pattern.len())
-                    .any(|window| window == pattern.as_bytes())
-            })
-            .map(move |(key, value)| {
-                if highlight_matched {
-                    let highlighted_value = highlight_pattern(pattern, value.into_vec());
-                    (key.to_vec(), highlighted_value)
-                } else {
-                    (key.to_vec(), value.to_vec())
-                }
-            });
-        Ok(results)
+        let iter = self.iter_current_cf(IterBounds::default(), reverse, retry)?;
+        Ok(search_matches(
+            iter,
+            pattern,
+            SearchTarget::Key,
+            highlight_matched,
+        ))
     }
 
     pub fn search_value(
         &self,
-        pattern: &str,
+        pattern: &SearchPattern,
         highlight_matched: bool,
+        reverse: bool,
+        retry: usize,
     ) -> Result<impl Iterator<Item = (Vec<u8>, Vec<u8>)>> {
-        // let mut results = Vec::with_capacity(limit);
-        let cf = self.get_cf_handle(&self.current_cf).unwrap();
-        let iter = self.db.iterator_cf(cf, IteratorMode::Start);
-        let results = iter
-            .filter_map(|item| item.ok())
-            .filter(|value| {
-                value
-                    .1
-                    .windows(pattern.len())
-                    .any(|window| window == pattern.as_bytes())
+        let iter = self.iter_current_cf(IterBounds::default(), reverse, retry)?;
+        Ok(search_matches(
+            iter,
+            pattern,
+            SearchTarget::Value,
+            highlight_matched,
+        ))
+    }
+
+    /// Runs `scan` over each of `cfs` on up to `concurrency` threads. Column families are
+    /// read independently, the results are returned in the order of `cfs`.
+    pub fn scan_cfs<T, F>(
+        &self,
+        cfs: &[String],
+        concurrency: usize,
+        reverse: bool,
+        retry: usize,
+        scan: F,
+    ) -> Result<Vec<(String, T)>>
+    where
+        T: Send,
+        F: Fn(RetryIter<'_>) -> T + Sync,
+    {
+        let handles = cfs
+            .iter()
+            .map(|name| {
+                self.get_cf_handle(name)
+                    .ok_or_else(|| CliError::CfNotFound(name.clone()))
             })
-            .map(move |(key, value)| {
-                if highlight_matched {
-                    let highlighted_value = highlight_pattern(pattern, value.into_vec());
-                    (key.to_vec(), highlighted_value)
-                } else {
-                    (key.to_vec(), value.to_vec())
+            .collect::<Result<Vec<_>>>()?;
+        // DBHelper isn't Sync because of its counters, so the workers only get the DB
+        let db = &self.db;
+        let next = AtomicUsize::new(0);
+        let mut results: Vec<(usize, T, ScanStats)> = thread::scope(|scope| {
+            let workers: Vec<_> = (0..concurrency.clamp(1, handles.len().max(1)))
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut done = Vec::new();
+                        loop {
+                            let index = next.fetch_add(1, atomic::Ordering::Relaxed);
+                            let Some(cf) = handles.get(index) else {
+                                break done;
+                            };
+                            let stats = ScanStats::default();
+                            let iter = RetryIter::new(
+                                db,
+                                cf,
+                                IterBounds::default(),
+                                reverse,
+                                retry,
+                                &stats,
+                            );
+                            let result = scan(iter);
+                            done.push((index, result, stats));
+                        }
+                    })
+                })
+                .collect();
+            workers
+                .into_iter()
+                .flat_map(|worker| {
+                    worker
+                        .join()
+                        .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+                })
+                .collect()
+        });
+        results.sort_by_key(|(index, ..)| *index);
+        self.scan_stats.reset();
+        let mut ordered = Vec::with_capacity(results.len());
+        for (index, result, stats) in results {
+            self.scan_stats.add(&stats);
+            ordered.push((cfs[index].clone(), result));
+        }
+        Ok(ordered)
+    }
+
+    /// Compares the current column family with `other` by walking both in key order
+    pub fn diff_cf(&self, other: &str) -> Result<impl Iterator<Item = DiffEntry>> {
+        let Some(other_cf) = self.get_cf_handle(other) else {
+            return Err(CliError::CfNotFound(other.to_string()));
+        };
+        let mut current = self
+            .iter_current_cf(IterBounds::default(), false, 0)?
+            .peekable();
+        let mut other = RetryIter::new(
+            &self.db,
+            other_cf,
+            IterBounds::default(),
+            false,
+            0,
+            &self.scan_stats,
+        )
+        .peekable();
+        let entries = std::iter::from_fn(move || {
+            loop {
+                let ordering = match (current.peek(), other.peek()) {
+                    (None, None) => return None,
+                    (Some(_), None) => Ordering::Less,
+                    (None, Some(_)) => Ordering::Greater,
+                    (Some((current_key, _)), Some((other_key, _))) => current_key.cmp(other_key),
+                };
+                match ordering {
+                    Ordering::Less => {
+                        let (key, value) = current.next()?;
+                        return Some(DiffEntry::Removed(key, value));
+                    }
+                    Ordering::Greater => {
+                        let (key, value) = other.next()?;
+                        return Some(DiffEntry::Added(key, value));
+                    }
+                    Ordering::Equal => {
+                        let (key, current_value) = current.next()?;
+                        let (_, other_value) = other.next()?;
+                        if current_value != other_value {
+                            return Some(DiffEntry::Changed(key, current_value, other_value));
+                        }
+                    }
                 }
-            });
-        Ok(results)
+            }
+        });
+        Ok(entries)
     }
 
-    pub fn delete(&self, key: &str) -> Result<()> {
-        let cf = self.get_cf_handle(&self.current_cf).unwrap();
-        match self.db.delete_cf(cf, key) {
-            Ok(_) => println!("Key deleted successfully"),
-            Err(_) => println!("Key not found"),
-        }
+    pub fn delete(&self, key: &[u8], write_options: &WriteOptions) -> Result<()> {
+        let cf = self.current_cf_handle()?;
+        self.record_write();
+        // RocksDB doesn't report missing keys, an error is a failed write
+        self.db.delete_cf_opt(cf, key, write_options)?;
+        println!("Key deleted successfully");
         Ok(())
     }
 
     pub fn scan(
         &self,
-        start: Option<&str>,
-        end: Option<&str>,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
         reverse: bool,
+        retry: usize,
     ) -> Result<impl Iterator<Item = (Vec<u8>, Vec<u8>)>> {
-        let cf = self.get_cf_handle(&self.current_cf).unwrap();
-        let mut options = rocksdb::ReadOptions::default();
-        if let Some(start) = start {
-            options.set_iterate_lower_bound(start.as_bytes());
-        }
-        if let Some(end) = end {
-            options.set_iterate_upper_bound(end.as_bytes());
-        }
-        let iter = self.db.iterator_cf_opt(
-            cf,
-            options,
-            if reverse {
-                IteratorMode::End
-            } else {
-                IteratorMode::Start
-            },
-        );
-        let key_values = iter
-            .filter_map(|kv| kv.ok())
-            .map(|(key, value)| (key.into(), value.into()));
+        let bounds = IterBounds {
+            lower: start.map(|start| start.to_vec()),
+            upper: end.map(|end| end.to_vec()),
+            ..Default::default()
+        };
+        let iter = self.iter_current_cf(bounds, reverse, retry)?;
+        let key_values = iter.map(|(key, value)| (key.into(), value.into()));
         Ok(key_values)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::SearchPattern;
+
+    #[test]
+    fn empty_literal_pattern_matches_everything() {
+        let pattern = SearchPattern::new("", false).unwrap();
+        assert!(pattern.is_match(b""));
+        assert!(pattern.is_match(b"key"));
+    }
+
+    #[test]
+    fn literal_pattern_matches_substrings() {
+        let pattern = SearchPattern::new("ey", false).unwrap();
+        assert!(pattern.is_match(b"key"));
+        assert!(!pattern.is_match(b"e"));
+    }
+}
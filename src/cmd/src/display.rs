@@ -1,70 +1,806 @@
 use anyhow::Result;
+use base64::prelude::{BASE64_STANDARD, Engine};
+use colored::Colorize;
 use comfy_table::{Cell, Color, Table};
 use rocksdb::DB;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::db::{DiffEntry, KeyLookup, KeyTimestamp, ReadAmpStats, SstSummary};
+use crate::export::{HEX_ENCODING, UTF8_ENCODING};
+use crate::utility::format_duration;
+use crate::wal::WalBatch;
 const BATH_ROWS: usize = 100;
+/// Shown in place of the value of a key that doesn't exist
+const NOT_FOUND: &str = "[NOT FOUND]";
+/// Encoding of binary keys and values in the JSON and YAML formats
+const BASE64_ENCODING: &str = "base64";
+
+/// How lists of key-value pairs are printed
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    #[default]
+    Table,
+    /// A YAML list of key/value mappings, `key_encoding` and `value_encoding` say whether each
+    /// one is written as `utf8` text, `hex`, `base64` for binary data or an `int`
+    Yaml,
+    /// A JSON array of key/value objects, encoded like the YAML ones
+    Json,
+    /// One JSON object per line, printed as the entries are read
+    Jsonl,
+    /// Tab-separated key and value columns after a header row, as shown in the table
+    Tsv,
+}
+
+/// Per-command options controlling how key-value pairs are rendered
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DisplayOptions {
+    /// Add a column with the value as stored, before unescaping
+    pub show_raw: bool,
+    pub format: OutputFormat,
+    /// Show values of the matching width as integers
+    pub decode_value: Option<IntDecoding>,
+    pub hex_keys: bool,
+    pub hex_values: bool,
+}
+
+/// `data` as uppercase hex if `hex` is set, as text otherwise
+pub fn format_bytes(data: &[u8], hex: bool) -> String {
+    if hex {
+        hex::encode_upper(data)
+    } else {
+        String::from_utf8_lossy(data).into_owned()
+    }
+}
+
+/// Text of the value column, as hex, a decoded integer or the unescaped value
+fn value_cell(value: &[u8], options: DisplayOptions) -> String {
+    if options.hex_values {
+        return format_bytes(value, true);
+    }
+    decoded_value(value, options).unwrap_or_else(|| value_to_string(value))
+}
+
+/// A fixed-width integer encoding, parsed from `<int|uint>:<be|le>:<1|2|4|8>`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IntDecoding {
+    pub signed: bool,
+    pub little_endian: bool,
+    pub width: usize,
+}
+
+impl IntDecoding {
+    /// The value as a number, `None` if it isn't `width` bytes long
+    pub fn decode(&self, value: &[u8]) -> Option<String> {
+        if value.len() != self.width {
+            return None;
+        }
+        let mut bytes = [0u8; 8];
+        let unsigned = if self.little_endian {
+            bytes[..self.width].copy_from_slice(value);
+            u64::from_le_bytes(bytes)
+        } else {
+            bytes[8 - self.width..].copy_from_slice(value);
+            u64::from_be_bytes(bytes)
+        };
+        if self.signed {
+            // move the sign bit to the top so the shift back extends it
+            let shift = 64 - 8 * self.width as u32;
+            Some(((unsigned << shift) as i64 >> shift).to_string())
+        } else {
+            Some(unsigned.to_string())
+        }
+    }
+}
+
+impl FromStr for IntDecoding {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.split(':').collect();
+        let [kind, endian, width] = parts[..] else {
+            return Err(format!("Expected <int|uint>:<be|le>:<width>, got {}", s));
+        };
+        let signed = match kind {
+            "int" => true,
+            "uint" => false,
+            _ => {
+                return Err(format!(
+                    "Unknown integer type {}, expected int or uint",
+                    kind
+                ));
+            }
+        };
+        let little_endian = match endian {
+            "be" => false,
+            "le" => true,
+            _ => return Err(format!("Unknown byte order {}, expected be or le", endian)),
+        };
+        let width = match width.parse() {
+            Ok(width @ (1 | 2 | 4 | 8)) => width,
+            _ => return Err(format!("Invalid width {}, expected 1, 2, 4 or 8", width)),
+        };
+        Ok(IntDecoding {
+            signed,
+            little_endian,
+            width,
+        })
+    }
+}
+
+/// Decoders for values that are themselves structured
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ValueView {
+    /// A JSON value shown as an indented tree
+    JsonTree,
+    /// A sequence of varint32 length-prefixed sub-key and sub-value pairs, as written by
+    /// RocksDB's `PutLengthPrefixedSlice`
+    LenPrefixedPairs,
+}
+
+pub fn print_value_view(value: &[u8], view: ValueView) -> Result<()> {
+    match view {
+        ValueView::JsonTree => {
+            let json: serde_json::Value = serde_json::from_slice(value)?;
+            let mut lines = Vec::new();
+            json_tree_lines(&json, 0, &mut lines);
+            for line in lines {
+                println!("{}", line);
+            }
+        }
+        ValueView::LenPrefixedPairs => {
+            let mut table = Table::new();
+            table.set_content_arrangement(comfy_table::ContentArrangement::DynamicFullWidth);
+            table.set_header(key_value_header("Sub-key", DisplayOptions::default()));
+            for (key, value) in decode_len_prefixed_pairs(value)? {
+                table.add_row(vec![
+                    String::from_utf8_lossy(key).into_owned(),
+                    value_to_string(value),
+                ]);
+            }
+            println!("{table}");
+        }
+    }
+    Ok(())
+}
+
+fn json_tree_lines(value: &serde_json::Value, depth: usize, lines: &mut Vec<String>) {
+    let indent = "  ".repeat(depth);
+    let mut child = |label: String, value: &serde_json::Value| match value {
+        serde_json::Value::Object(_) | serde_json::Value::Array(_) => {
+            lines.push(format!("{}{}", indent, label.bright_cyan()));
+            json_tree_lines(value, depth + 1, lines);
+        }
+        scalar => lines.push(format!("{}{} {}", indent, label.bright_cyan(), scalar)),
+    };
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, value) in map {
+                child(format!("{}:", key), value);
+            }
+        }
+        serde_json::Value::Array(items) => {
+            for (index, value) in items.iter().enumerate() {
+                child(format!("[{}]", index), value);
+            }
+        }
+        scalar => lines.push(format!("{}{}", indent, scalar)),
+    }
+}
+
+/// Splits `value` into the sub-key and sub-value pairs it encodes
+fn decode_len_prefixed_pairs(value: &[u8]) -> Result<Vec<(&[u8], &[u8])>> {
+    let mut rest = value;
+    let mut pairs = Vec::new();
+    while !rest.is_empty() {
+        let key = take_len_prefixed(value.len(), &mut rest)?;
+        let value = take_len_prefixed(value.len(), &mut rest)?;
+        pairs.push((key, value));
+    }
+    Ok(pairs)
+}
+
+/// Takes one varint32 length-prefixed slice off the front of `rest`
+fn take_len_prefixed<'a>(total_len: usize, rest: &mut &'a [u8]) -> Result<&'a [u8]> {
+    let offset = total_len - rest.len();
+    let mut len = 0usize;
+    let mut shift = 0;
+    loop {
+        let Some((&byte, tail)) = rest.split_first() else {
+            anyhow::bail!("Truncated length at byte {}", offset);
+        };
+        *rest = tail;
+        len |= ((byte & 0x7f) as usize) << shift;
+        if byte & 0x80 == 0 {
+            break;
+        }
+        shift += 7;
+        if shift > 28 {
+            anyhow::bail!("Invalid length at byte {}", offset);
+        }
+    }
+    if rest.len() < len {
+        anyhow::bail!(
+            "Length {} at byte {} runs past the end of the value",
+            len,
+            offset
+        );
+    }
+    let (bytes, tail) = rest.split_at(len);
+    *rest = tail;
+    Ok(bytes)
+}
+
+/// The value decoded as an integer, if `options` asks for it and the width matches
+fn decoded_value(value: &[u8], options: DisplayOptions) -> Option<String> {
+    options.decode_value?.decode(value)
+}
+
+fn key_value_header(key_column: &str, options: DisplayOptions) -> Vec<Cell> {
+    let mut names = vec![key_column, "Value"];
+    if options.show_raw {
+        names.push("Raw Value");
+    }
+    names
+        .into_iter()
+        .map(|name| {
+            Cell::new(name)
+                .add_attribute(comfy_table::Attribute::Bold)
+                .set_alignment(comfy_table::CellAlignment::Center)
+                .fg(Color::Green)
+        })
+        .collect()
+}
+
+fn raw_value_to_string(value: &[u8]) -> String {
+    match std::str::from_utf8(value) {
+        Ok(s) => s.to_string(),
+        Err(_) => format!("[BINARY] {}", hex::encode(value)),
+    }
+}
+
+fn value_to_string(value: &[u8]) -> String {
+    match std::str::from_utf8(value) {
+        Ok(s) => match unescaper::unescape(s) {
+            Ok(es) => es,
+            Err(_) => s.to_string(),
+        },
+        Err(_) => format!("[BINARY] {}", hex::encode(value)),
+    }
+}
 
-pub fn print_key_value(key: &[u8], value: &[u8]) {
+/// Prints key-value pairs in the output format of `options`
+pub fn render<T: Iterator<Item = (Vec<u8>, Vec<u8>)>>(entries: T, options: DisplayOptions) {
+    match options.format {
+        OutputFormat::Table => print_key_value_list(entries, options),
+        format => print_records(
+            entries.map(|(key, value)| entry_record(None, Some(&key), Some(&value), options)),
+            format,
+        ),
+    }
+}
+
+/// Prints the entries of several column families, in one list with a `cf` field per entry
+/// unless the format is a table
+pub fn render_cf_entries(results: Vec<(String, Vec<Entry>)>, options: DisplayOptions) {
+    if options.format == OutputFormat::Table {
+        for (cf, entries) in results {
+            println!("Column family {}", cf.bright_green());
+            render(entries.into_iter(), options);
+        }
+        return;
+    }
+    let records = results.iter().flat_map(|(cf, entries)| {
+        entries
+            .iter()
+            .map(|(key, value)| entry_record(Some(cf), Some(key), Some(value), options))
+    });
+    print_records(records, options.format);
+}
+
+type Record = serde_json::Map<String, serde_json::Value>;
+
+type Entry = (Vec<u8>, Vec<u8>);
+
+/// An entry as a record of the non-table formats, `None` fields are left out of it, except a
+/// missing value which is a key that wasn't found
+fn entry_record(
+    cf: Option<&str>,
+    key: Option<&[u8]>,
+    value: Option<&[u8]>,
+    options: DisplayOptions,
+) -> Record {
+    let mut record = Record::new();
+    if let Some(cf) = cf {
+        record.insert("cf".to_string(), cf.into());
+    }
+    if let Some(key) = key {
+        insert_key(&mut record, key, options);
+    }
+    if options.format == OutputFormat::Tsv {
+        // the same columns as the table
+        let value_cell =
+            value.map_or_else(|| NOT_FOUND.to_string(), |value| value_cell(value, options));
+        record.insert("value".to_string(), value_cell.into());
+        if options.show_raw {
+            record.insert(
+                "raw_value".to_string(),
+                value.map(raw_value_to_string).into(),
+            );
+        }
+        return record;
+    }
+    let Some(value) = value else {
+        let mut names = vec!["value", "value_encoding"];
+        if options.show_raw {
+            names.extend(["raw_value", "raw_value_encoding"]);
+        }
+        for name in names {
+            record.insert(name.to_string(), serde_json::Value::Null);
+        }
+        return record;
+    };
+    match decoded_value(value, options).filter(|_| !options.hex_values) {
+        Some(number) => {
+            let number = number
+                .parse::<serde_json::Number>()
+                .map_or_else(|_| number.into(), serde_json::Value::Number);
+            record.insert("value".to_string(), number);
+            record.insert("value_encoding".to_string(), "int".into());
+        }
+        None if !options.hex_values && std::str::from_utf8(value).is_ok() => {
+            record.insert("value".to_string(), value_to_string(value).into());
+            record.insert("value_encoding".to_string(), UTF8_ENCODING.into());
+        }
+        None => insert_bytes(&mut record, "value", value, options.hex_values),
+    }
+    if options.show_raw {
+        insert_bytes(&mut record, "raw_value", value, false);
+    }
+    record
+}
+
+/// Adds the key to `record`, as in the table for TSV and with its encoding otherwise
+fn insert_key(record: &mut Record, key: &[u8], options: DisplayOptions) {
+    if options.format == OutputFormat::Tsv {
+        record.insert(
+            "key".to_string(),
+            format_bytes(key, options.hex_keys).into(),
+        );
+    } else {
+        insert_bytes(record, "key", key, options.hex_keys);
+    }
+}
+
+/// Adds `data` to `record` as `name` with a `{name}_encoding` field, in hex if `hex` is set,
+/// as text if it is UTF-8 and in base64 otherwise
+fn insert_bytes(record: &mut Record, name: &str, data: &[u8], hex: bool) {
+    let (text, encoding) = match std::str::from_utf8(data) {
+        _ if hex => (format_bytes(data, true), HEX_ENCODING),
+        Ok(text) => (text.to_string(), UTF8_ENCODING),
+        Err(_) => (BASE64_STANDARD.encode(data), BASE64_ENCODING),
+    };
+    record.insert(name.to_string(), text.into());
+    record.insert(format!("{name}_encoding"), encoding.into());
+}
+
+/// Prints `records` in one of the YAML, JSON and TSV formats as soon as they are read,
+/// tab-separated columns are named after the fields of the first record
+fn print_records<T: Iterator<Item = Record>>(records: T, format: OutputFormat) {
+    let mut tsv = csv::WriterBuilder::new()
+        .delimiter(b'\t')
+        .from_writer(std::io::stdout());
+    let mut count = 0;
+    for record in records {
+        match format {
+            // one-item lists, so the whole output is one list
+            OutputFormat::Yaml => match serde_yaml_ng::to_string(&[record]) {
+                Ok(yaml) => print!("{yaml}"),
+                Err(e) => eprintln!("Failed to serialize entry: {}", e),
+            },
+            OutputFormat::Json => {
+                let separator = if count == 0 { "[\n  " } else { ",\n  " };
+                print!("{separator}{}", serde_json::Value::Object(record));
+            }
+            OutputFormat::Tsv => {
+                if let Err(e) = write_tsv_record(&mut tsv, &record, count == 0) {
+                    eprintln!("Failed to write entry: {}", e);
+                    return;
+                }
+            }
+            OutputFormat::Jsonl | OutputFormat::Table => {
+                println!("{}", serde_json::Value::Object(record))
+            }
+        }
+        count += 1;
+    }
+    match format {
+        OutputFormat::Yaml if count == 0 => println!("[]"),
+        OutputFormat::Json if count == 0 => println!("[]"),
+        OutputFormat::Json => println!("\n]"),
+        OutputFormat::Tsv => {
+            if let Err(e) = tsv.flush() {
+                eprintln!("Failed to write entry: {}", e);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Writes `record` as a tab-separated row, after a header row of its field names if `header`
+/// is set, quoting fields that contain tabs or line breaks
+fn write_tsv_record<W: std::io::Write>(
+    writer: &mut csv::Writer<W>,
+    record: &Record,
+    header: bool,
+) -> csv::Result<()> {
+    if header {
+        writer.write_record(record.keys())?;
+    }
+    writer.write_record(record.values().map(|value| match value {
+        serde_json::Value::String(text) => text.clone(),
+        serde_json::Value::Null => String::new(),
+        value => value.to_string(),
+    }))
+}
+
+pub fn print_key_value(key: &[u8], value: &[u8], options: DisplayOptions) {
+    if options.format != OutputFormat::Table {
+        return render(std::iter::once((key.to_vec(), value.to_vec())), options);
+    }
     let mut table = Table::new();
     table.set_content_arrangement(comfy_table::ContentArrangement::DynamicFullWidth);
-    table.set_header(vec![
-        Cell::new("Key")
-            .add_attribute(comfy_table::Attribute::Bold)
-            .set_alignment(comfy_table::CellAlignment::Center)
-            .fg(Color::Green),
-        Cell::new("Value")
-            .add_attribute(comfy_table::Attribute::Bold)
-            .set_alignment(comfy_table::CellAlignment::Center)
-            .fg(Color::Green),
-    ]);
-    table.add_row(vec![
-        Cell::new(String::from_utf8_lossy(key)),
-        Cell::new(
-            match unescaper::unescape(String::from_utf8_lossy(value).as_ref()) {
-                Ok(s_value) => s_value,
-                Err(_) => String::from_utf8_lossy(value).to_string(),
-            },
-        ),
-    ]);
+    table.set_header(key_value_header("Key", options));
+    let mut row = vec![
+        Cell::new(format_bytes(key, options.hex_keys)),
+        Cell::new(value_cell(value, options)),
+    ];
+    if options.show_raw {
+        row.push(Cell::new(raw_value_to_string(value)));
+    }
+    table.add_row(row);
     println!("{table}");
 }
 
-pub fn print_key_value_list<T: Iterator<Item = (Vec<u8>, Vec<u8>)>>(entries: T) {
+pub fn print_key_value_list<T: Iterator<Item = (Vec<u8>, Vec<u8>)>>(
+    entries: T,
+    options: DisplayOptions,
+) {
     let mut table = Table::new();
     table.set_content_arrangement(comfy_table::ContentArrangement::DynamicFullWidth);
-    table.set_header(vec![
-        Cell::new("Key")
-            .add_attribute(comfy_table::Attribute::Bold)
-            .set_alignment(comfy_table::CellAlignment::Center)
-            .fg(Color::Green),
-        Cell::new("Value")
-            .add_attribute(comfy_table::Attribute::Bold)
-            .set_alignment(comfy_table::CellAlignment::Center)
-            .fg(Color::Green),
-    ]);
+    table.set_header(key_value_header("Key", options));
     table.set_row_capacity(BATH_ROWS);
     let mut row_count = 0;
     for (key, value) in entries {
-        let key_str = String::from_utf8_lossy(&key).into_owned();
-        let value_str = match std::str::from_utf8(&value) {
-            Ok(s) => match unescaper::unescape(s) {
-                Ok(es) => es,
-                Err(_) => s.to_string(),
-            },
-            Err(_) => format!("[BINARY] {}", hex::encode(value)),
-        };
-        table.add_row(vec![key_str, value_str]);
+        let key_str = format_bytes(&key, options.hex_keys);
+        let value_str = value_cell(&value, options);
+        let mut row = vec![key_str, value_str];
+        if options.show_raw {
+            row.push(raw_value_to_string(&value));
+        }
+        table.add_row(row);
         row_count += 1;
         if row_count % BATH_ROWS == 0 {
             println!("{table}");
             table.clear_rows();
         }
     }
+    if row_count == 0 {
+        print_no_entries();
+    } else if !table.is_empty() {
+        println!("{table}");
+    }
+}
+
+/// Prints the value of one key in several column families
+pub fn print_cf_values(entries: &[(String, Option<Vec<u8>>)], options: DisplayOptions) {
+    if options.format != OutputFormat::Table {
+        let records = entries
+            .iter()
+            .map(|(cf, value)| entry_record(Some(cf), None, value.as_deref(), options));
+        return print_records(records, options.format);
+    }
+    let rows = entries
+        .iter()
+        .map(|(cf, value)| (cf.clone(), value.as_deref()));
+    print_optional_values("Column Family", rows, options);
+}
+
+/// Prints the values of `multi-get` in the order of the keys, including the missing ones
+pub fn print_multi_get(entries: &[KeyLookup], options: DisplayOptions) {
+    if options.format != OutputFormat::Table {
+        let records = entries
+            .iter()
+            .map(|(key, value)| entry_record(None, Some(key), value.as_deref(), options));
+        return print_records(records, options.format);
+    }
+    let rows = entries
+        .iter()
+        .map(|(key, value)| (format_bytes(key, options.hex_keys), value.as_deref()));
+    print_optional_values("Key", rows, options);
+}
+
+fn print_optional_values<'a, T: Iterator<Item = (String, Option<&'a [u8]>)>>(
+    key_column: &str,
+    rows: T,
+    options: DisplayOptions,
+) {
+    let mut table = Table::new();
+    table.set_content_arrangement(comfy_table::ContentArrangement::DynamicFullWidth);
+    table.set_header(key_value_header(key_column, options));
+    for (name, value) in rows {
+        let mut row = vec![Cell::new(name)];
+        match value {
+            Some(value) => {
+                row.push(Cell::new(value_cell(value, options)));
+                if options.show_raw {
+                    row.push(Cell::new(raw_value_to_string(value)));
+                }
+            }
+            None => row.push(Cell::new(NOT_FOUND).fg(Color::DarkGrey)),
+        }
+        table.add_row(row);
+    }
+    println!("{table}");
+}
+
+/// Prints the keys of `keys`, one per line with tables
+pub fn print_keys<T: Iterator<Item = Box<[u8]>>>(keys: T, options: DisplayOptions) {
+    if options.format != OutputFormat::Table {
+        let records = keys.map(|key| {
+            let mut record = Record::new();
+            insert_key(&mut record, &key, options);
+            record
+        });
+        return print_records(records, options.format);
+    }
+    let mut keys = keys.peekable();
+    if keys.peek().is_none() {
+        print_no_entries();
+    }
+    for key in keys {
+        println!("{}", format_bytes(&key, options.hex_keys).bright_green());
+    }
+}
+
+/// Prints that `key` doesn't exist, as a record with a `null` value in the formats other than
+/// the table
+pub fn print_missing_key(key: &[u8], options: DisplayOptions) {
+    if options.format == OutputFormat::Table {
+        return println!("Key not found");
+    }
+    print_records(
+        std::iter::once(entry_record(None, Some(key), None, options)),
+        options.format,
+    );
+}
+
+/// Printed instead of an empty table, so an empty result doesn't look like a failure
+pub fn print_no_entries() {
+    println!("(no matching entries)");
+}
+
+pub fn print_wal_batches<T: Iterator<Item = WalBatch>>(batches: T) {
+    let mut table = Table::new();
+    table.set_content_arrangement(comfy_table::ContentArrangement::DynamicFullWidth);
+    table.set_header(vec!["Sequence", "Operation", "Column Family", "Key", "Value"]);
+    table.set_row_capacity(BATH_ROWS);
+    let mut row_count = 0;
+    for batch in batches {
+        let undecoded = batch.count - batch.ops.len();
+        let batch_seqs = format!("{}-{}", batch.seq, batch.last_seq());
+        for op in batch.ops {
+            let (operation, value) = match op.value {
+                Some(value) => (Cell::new("PUT").fg(Color::Green), value_to_string(&value)),
+                None => (Cell::new("DELETE").fg(Color::Red), String::new()),
+            };
+            let seq = op
+                .seq
+                .map_or_else(|| batch_seqs.clone(), |seq| seq.to_string());
+            table.add_row(vec![
+                Cell::new(seq),
+                operation,
+                Cell::new("default"),
+                Cell::new(String::from_utf8_lossy(&op.key)),
+                Cell::new(value),
+            ]);
+            row_count += 1;
+        }
+        if undecoded > 0 {
+            table.add_row(vec![
+                Cell::new(batch_seqs),
+                Cell::new(format!("{} more", undecoded)).fg(Color::Yellow),
+                Cell::new("(unknown)"),
+                Cell::new(
+                    "merges, single deletes, range deletes and operations on other column \
+                     families can't be decoded",
+                ),
+                Cell::new(""),
+            ]);
+            row_count += 1;
+        }
+        if row_count >= BATH_ROWS {
+            println!("{table}");
+            table.clear_rows();
+            row_count = 0;
+        }
+    }
+    if !table.is_empty() {
+        println!("{table}");
+    }
+}
+
+pub fn print_cf_diff<T: Iterator<Item = DiffEntry>>(entries: T, current: &str, other: &str) {
+    let with_color = colored::control::SHOULD_COLORIZE.should_colorize();
+    let paint = |text: String, color: Color| {
+        if with_color {
+            Cell::new(text).fg(color)
+        } else {
+            Cell::new(text)
+        }
+    };
+    let mut table = Table::new();
+    table.set_content_arrangement(comfy_table::ContentArrangement::DynamicFullWidth);
+    table.set_header(vec!["", "Key", current, other]);
+    table.set_row_capacity(BATH_ROWS);
+    let (mut added, mut removed, mut changed) = (0, 0, 0);
+    for entry in entries {
+        let row = match entry {
+            DiffEntry::Added(key, value) => {
+                added += 1;
+                vec![
+                    paint("+".to_string(), Color::Green),
+                    paint(String::from_utf8_lossy(&key).into_owned(), Color::Green),
+                    Cell::new(""),
+                    paint(value_to_string(&value), Color::Green),
+                ]
+            }
+            DiffEntry::Removed(key, value) => {
+                removed += 1;
+                vec![
+                    paint("-".to_string(), Color::Red),
+                    paint(String::from_utf8_lossy(&key).into_owned(), Color::Red),
+                    paint(value_to_string(&value), Color::Red),
+                    Cell::new(""),
+                ]
+            }
+            DiffEntry::Changed(key, current_value, other_value) => {
+                changed += 1;
+                vec![
+                    paint("~".to_string(), Color::Yellow),
+                    paint(String::from_utf8_lossy(&key).into_owned(), Color::Yellow),
+                    paint(value_to_string(&current_value), Color::Yellow),
+                    paint(value_to_string(&other_value), Color::Yellow),
+                ]
+            }
+        };
+        table.add_row(row);
+        if (added + removed + changed) % BATH_ROWS == 0 {
+            println!("{table}");
+            table.clear_rows();
+        }
+    }
     if !table.is_empty() {
         println!("{table}");
     }
+    println!(
+        "{} added, {} removed, {} changed",
+        added.to_string().green(),
+        removed.to_string().red(),
+        changed.to_string().yellow()
+    );
 }
 
-pub fn print_column_families(cfs: &[String], current: &str) {
+pub fn print_ttl_report(entries: &[KeyTimestamp], ttl: Duration) {
+    let now = SystemTime::now();
+    let mut table = Table::new();
+    table.set_content_arrangement(comfy_table::ContentArrangement::DynamicFullWidth);
+    table.set_header(vec!["Key", "Written (unix time)", "Age", "Status"]);
+    let mut expired = 0;
+    for (key, written) in entries {
+        let key = Cell::new(String::from_utf8_lossy(key));
+        let Some(written) = written else {
+            table.add_row(vec![
+                key,
+                Cell::new("invalid timestamp").fg(Color::Yellow),
+                Cell::new(""),
+                Cell::new(""),
+            ]);
+            continue;
+        };
+        let unix_time = written
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let age = now.duration_since(*written).unwrap_or_default();
+        let status = if age > ttl {
+            expired += 1;
+            Cell::new("Expired").fg(Color::Red)
+        } else {
+            Cell::new(format!("Live, expires in {}", format_duration(ttl - age))).fg(Color::Green)
+        };
+        table.add_row(vec![
+            key,
+            Cell::new(unix_time),
+            Cell::new(format_duration(age)),
+            status,
+        ]);
+    }
+    println!("{table}");
+    println!(
+        "{} of {} sampled keys are older than the TTL of {}",
+        expired.to_string().bright_red(),
+        entries.len(),
+        format_duration(ttl)
+    );
+}
+
+/// Prints the write time of a value stored in TTL mode and when it expires
+pub fn print_value_ttl(written: SystemTime, ttl: Duration) {
+    let unix_time = written
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let age = SystemTime::now()
+        .duration_since(written)
+        .unwrap_or_default();
+    println!(
+        "Written at {} (unix time), {} ago",
+        unix_time.to_string().bright_green(),
+        format_duration(age)
+    );
+    if age > ttl {
+        println!(
+            "Expired {} ago, dropped at the next compaction",
+            format_duration(age - ttl).bright_red()
+        );
+    } else {
+        println!("Expires in {}", format_duration(ttl - age).bright_green());
+    }
+}
+
+pub fn print_read_amp(stats: &ReadAmpStats) {
+    let per_key = |total: u64| {
+        if stats.keys == 0 {
+            String::new()
+        } else {
+            format!("{:.2}", total as f64 / stats.keys as f64)
+        }
+    };
+    let mut table = Table::new();
+    table.set_header(vec!["Counter", "Total", "Per key"]);
+    for (name, total) in [
+        ("Block reads", stats.block_reads),
+        ("Block bytes read", stats.block_read_bytes),
+        ("Block cache hits", stats.block_cache_hits),
+        ("Memtable lookups", stats.memtable_gets),
+        ("SST bloom filter passes", stats.bloom_sst_hits),
+        ("SST bloom filter rejections", stats.bloom_sst_misses),
+    ] {
+        table.add_row(vec![
+            Cell::new(name),
+            Cell::new(total),
+            Cell::new(per_key(total)),
+        ]);
+    }
+    println!("{table}");
+    println!(
+        "Read {} of {} sampled keys",
+        stats.found.to_string().bright_green(),
+        stats.keys
+    );
+}
+
+pub fn print_column_families(cfs: &[String], current: &str, format: OutputFormat) {
+    if format != OutputFormat::Table {
+        let records = cfs.iter().map(|cf| {
+            let mut record = Record::new();
+            record.insert("name".to_string(), cf.as_str().into());
+            record.insert("current".to_string(), (cf == current).into());
+            record
+        });
+        return print_records(records, format);
+    }
     let mut table = Table::new();
     table.set_header(vec!["Column Family", "Status"]);
 
@@ -83,29 +819,172 @@ pub fn print_column_families(cfs: &[String], current: &str) {
     println!("{table}");
 }
 
-pub fn print_database_info(db: &DB, path: &str, current_cf: &str) -> Result<()> {
+/// Prints the statistics of the column family `cf` as a property table
+pub fn print_cf_stats(cf: &str, stats: &[(String, String)]) {
+    println!("Column family {}", cf.bright_green());
     let mut table = Table::new();
     table.set_header(vec!["Property", "Value"]);
+    for (property, value) in stats {
+        table.add_row(vec![property, value]);
+    }
+    println!("{table}");
+}
+
+/// Prints how a compaction changed the SST files of `cf`
+pub fn print_compaction(cf: &str, before: &SstSummary, after: &SstSummary, elapsed: Duration) {
+    println!(
+        "Compaction of {} finished in {:.2?}",
+        cf.bright_green(),
+        elapsed
+    );
+    let mut table = Table::new();
+    table.set_header(vec!["SST files", "Before", "After"]);
+    table.add_row(vec![
+        Cell::new("L0 Files"),
+        Cell::new(before.level0_files),
+        Cell::new(after.level0_files),
+    ]);
+    table.add_row(vec![
+        Cell::new("Total SST Size"),
+        Cell::new(format!("{} bytes", before.total_size)),
+        Cell::new(format!("{} bytes", after.total_size)),
+    ]);
+    println!("{table}");
+}
 
-    table.add_row(vec!["Path", path]);
-    table.add_row(vec!["Current Column Family", current_cf]);
+pub fn print_database_info(
+    db: &DB,
+    path: &str,
+    current_cf: &str,
+    format: OutputFormat,
+) -> Result<()> {
+    // (label, field name of the machine-readable formats, value)
+    let mut properties = vec![
+        ("Path", "path", path.to_string()),
+        (
+            "Current Column Family",
+            "current_cf",
+            current_cf.to_string(),
+        ),
+    ];
 
     if let Some(create_time) = db.property_value("rocksdb.creation-time")? {
-        table.add_row(vec!["Creation Time", &create_time]);
+        properties.push(("Creation Time", "creation_time", create_time));
     }
 
     if let Some(version) = db.property_value("rocksdb.version")? {
-        table.add_row(vec!["Version", &version]);
+        properties.push(("Version", "version", version));
     }
 
     if let Some(num_files) = db.property_value("rocksdb.num-files-at-level0")? {
-        table.add_row(vec!["L0 Files", &num_files]);
+        properties.push(("L0 Files", "l0_files", num_files));
     }
 
     if let Some(size) = db.property_value("rocksdb.total-sst-files-size")? {
-        table.add_row(vec!["Total SST Size", &format!("{} bytes", size)]);
+        properties.push(("Total SST Size", "total_sst_size", size));
     }
 
+    if format != OutputFormat::Table {
+        let record = properties
+            .into_iter()
+            .map(|(_, field, value)| (field.to_string(), value.into()))
+            .collect();
+        print_records(std::iter::once(record), format);
+        return Ok(());
+    }
+    let mut table = Table::new();
+    table.set_header(vec!["Property", "Value"]);
+    for (label, field, value) in properties {
+        if field == "total_sst_size" {
+            table.add_row(vec![label.to_string(), format!("{} bytes", value)]);
+        } else {
+            table.add_row(vec![label.to_string(), value]);
+        }
+    }
     println!("{table}");
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{IntDecoding, decode_len_prefixed_pairs};
+
+    fn decode(format: &str, value: &[u8]) -> Option<String> {
+        format.parse::<IntDecoding>().unwrap().decode(value)
+    }
+
+    #[test]
+    fn int_decoding_parses_its_format() {
+        assert_eq!(
+            "int:le:4".parse(),
+            Ok(IntDecoding {
+                signed: true,
+                little_endian: true,
+                width: 4,
+            })
+        );
+        assert!("int:be".parse::<IntDecoding>().is_err());
+        assert!("float:be:4".parse::<IntDecoding>().is_err());
+        assert!("int:me:4".parse::<IntDecoding>().is_err());
+        assert!("int:be:3".parse::<IntDecoding>().is_err());
+    }
+
+    #[test]
+    fn int_decoding_honours_the_byte_order() {
+        assert_eq!(decode("uint:be:2", &[0x01, 0x02]).as_deref(), Some("258"));
+        assert_eq!(decode("uint:le:2", &[0x01, 0x02]).as_deref(), Some("513"));
+        assert_eq!(
+            decode("uint:be:8", &[0xff; 8]).as_deref(),
+            Some("18446744073709551615")
+        );
+    }
+
+    #[test]
+    fn int_decoding_extends_the_sign() {
+        assert_eq!(decode("int:be:1", &[0xff]).as_deref(), Some("-1"));
+        assert_eq!(decode("int:le:2", &[0x00, 0x80]).as_deref(), Some("-32768"));
+        assert_eq!(
+            decode("int:be:4", &[0x7f, 0xff, 0xff, 0xff]).as_deref(),
+            Some("2147483647")
+        );
+        assert_eq!(decode("int:be:8", &[0xff; 8]).as_deref(), Some("-1"));
+    }
+
+    #[test]
+    fn int_decoding_skips_values_of_another_width() {
+        assert_eq!(decode("uint:be:4", &[0x01, 0x02]), None);
+        assert_eq!(decode("uint:be:4", &[]), None);
+    }
+
+    #[test]
+    fn len_prefixed_pairs_are_split() {
+        let value = [&[1u8][..], b"a", &[3], b"xyz", &[0], &[2], b"bc"].concat();
+        assert_eq!(
+            decode_len_prefixed_pairs(&value).unwrap(),
+            vec![(&b"a"[..], &b"xyz"[..]), (&b""[..], &b"bc"[..])]
+        );
+        assert!(decode_len_prefixed_pairs(&[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn len_prefixed_pairs_read_multi_byte_lengths() {
+        let long = vec![b'v'; 200];
+        let value = [&[1u8][..], b"k", &[0xc8, 0x01], &long].concat();
+        assert_eq!(
+            decode_len_prefixed_pairs(&value).unwrap(),
+            vec![(&b"k"[..], &long[..])]
+        );
+    }
+
+    #[test]
+    fn len_prefixed_pairs_reject_malformed_values() {
+        // key without a value
+        assert!(decode_len_prefixed_pairs(&[1, b'a']).is_err());
+        // length that never ends
+        assert!(decode_len_prefixed_pairs(&[0x80, 0x80]).is_err());
+        // length longer than a varint32
+        assert!(decode_len_prefixed_pairs(&[0x80, 0x80, 0x80, 0x80, 0x80, 0x01]).is_err());
+        // length past the end of the value
+        assert!(decode_len_prefixed_pairs(&[5, b'a', b'b']).is_err());
+    }
+}
@@ -0,0 +1,36 @@
+use thiserror::Error;
+
+/// Errors returned by the database layer of the library
+#[derive(Debug, Error)]
+pub enum CliError {
+    #[error("Failed to open database at {path}: {source}")]
+    DbOpen {
+        path: String,
+        #[source]
+        source: rocksdb::Error,
+    },
+    #[error("No column family {0}")]
+    CfNotFound(String),
+    #[error("Column family {0} already exists")]
+    CfExists(String),
+    #[error("The default column family can't be dropped")]
+    DropDefaultCf,
+    #[error("Column family {0} can't be copied onto itself")]
+    CopyToSelf(String),
+    #[error("The database is opened read-only")]
+    ReadOnly,
+    #[error("Invalid regex: {0}")]
+    InvalidRegex(#[from] regex::Error),
+    #[error("Invalid record on line {line}: {reason}")]
+    InvalidRecord { line: usize, reason: String },
+    #[error("Stopped at an unreadable entry after {0} entries, the result is incomplete")]
+    IncompleteScan(usize),
+    #[error("Invalid key: {0}")]
+    KeyDecode(String),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    RocksDb(#[from] rocksdb::Error),
+}
+
+pub type Result<T> = std::result::Result<T, CliError>;
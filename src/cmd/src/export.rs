@@ -0,0 +1,233 @@
+use crate::error::{CliError, Result};
+use std::io::{BufRead, Write};
+
+/// File formats of `export` and `import`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ExportFormat {
+    /// One JSON object per line, binary keys and values are hex with `encoding` set to `hex`
+    Json,
+    /// `key,value,encoding` rows, the encoding is `hex` when the key or value is binary
+    Csv,
+    /// Like csv, separated by tabs
+    Tsv,
+}
+
+pub const UTF8_ENCODING: &str = "utf8";
+pub const HEX_ENCODING: &str = "hex";
+
+/// The key and value as text with their encoding, hex when either of them is binary
+fn encode_entry(key: &[u8], value: &[u8]) -> (String, String, &'static str) {
+    match (std::str::from_utf8(key), std::str::from_utf8(value)) {
+        (Ok(key), Ok(value)) => (key.to_string(), value.to_string(), UTF8_ENCODING),
+        _ => (hex::encode(key), hex::encode(value), HEX_ENCODING),
+    }
+}
+
+/// A key-value pair as a JSON object with the `key`, `value` and `encoding` fields of the csv
+/// format
+fn entry_record(key: &[u8], value: &[u8]) -> serde_json::Map<String, serde_json::Value> {
+    let (key, value, encoding) = encode_entry(key, value);
+    let mut record = serde_json::Map::new();
+    record.insert("key".to_string(), key.into());
+    record.insert("value".to_string(), value.into());
+    record.insert("encoding".to_string(), encoding.into());
+    record
+}
+
+fn invalid_record(line: usize, reason: impl ToString) -> CliError {
+    CliError::InvalidRecord {
+        line,
+        reason: reason.to_string(),
+    }
+}
+
+fn record_field<'a>(
+    record: &'a serde_json::Map<String, serde_json::Value>,
+    name: &str,
+    line: usize,
+) -> Result<&'a str> {
+    record
+        .get(name)
+        .and_then(|value| value.as_str())
+        .ok_or_else(|| invalid_record(line, format!("no {name} field")))
+}
+
+/// The key and value of a JSON record, decoded according to its `encoding` field
+fn record_entry(
+    record: &serde_json::Map<String, serde_json::Value>,
+    line: usize,
+) -> Result<(Vec<u8>, Vec<u8>)> {
+    let key = record_field(record, "key", line)?;
+    let value = record_field(record, "value", line)?;
+    let encoding = match record.get("encoding") {
+        Some(_) => record_field(record, "encoding", line)?,
+        None => UTF8_ENCODING,
+    };
+    decode_entry(key, value, encoding, line)
+}
+
+/// The key and value of a record of any format, written in `encoding`
+fn decode_entry(key: &str, value: &str, encoding: &str, line: usize) -> Result<(Vec<u8>, Vec<u8>)> {
+    match encoding {
+        UTF8_ENCODING => Ok((key.as_bytes().to_vec(), value.as_bytes().to_vec())),
+        HEX_ENCODING => Ok((
+            hex::decode(key).map_err(|e| invalid_record(line, e))?,
+            hex::decode(value).map_err(|e| invalid_record(line, e))?,
+        )),
+        encoding => Err(invalid_record(line, format!("unknown encoding {encoding}"))),
+    }
+}
+
+fn delimiter(format: ExportFormat) -> u8 {
+    if format == ExportFormat::Tsv {
+        b'\t'
+    } else {
+        b','
+    }
+}
+
+/// Writes key-value pairs one record at a time
+pub enum RecordWriter<W: Write> {
+    Json(W),
+    Delimited(Box<csv::Writer<W>>),
+}
+
+impl<W: Write> RecordWriter<W> {
+    pub fn new(writer: W, format: ExportFormat) -> Result<Self> {
+        if format == ExportFormat::Json {
+            return Ok(RecordWriter::Json(writer));
+        }
+        let mut writer = csv::WriterBuilder::new()
+            .delimiter(delimiter(format))
+            .from_writer(writer);
+        writer
+            .write_record(["key", "value", "encoding"])
+            .map_err(std::io::Error::from)?;
+        Ok(RecordWriter::Delimited(Box::new(writer)))
+    }
+
+    pub fn write(&mut self, key: &[u8], value: &[u8]) -> Result<()> {
+        match self {
+            RecordWriter::Json(writer) => {
+                serde_json::to_writer(&mut *writer, &entry_record(key, value))
+                    .map_err(std::io::Error::from)?;
+                writeln!(writer)?;
+            }
+            RecordWriter::Delimited(writer) => {
+                let (key, value, encoding) = encode_entry(key, value);
+                writer
+                    .write_record([key.as_str(), value.as_str(), encoding])
+                    .map_err(std::io::Error::from)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn finish(self) -> Result<()> {
+        match self {
+            RecordWriter::Json(mut writer) => writer.flush()?,
+            RecordWriter::Delimited(mut writer) => writer.flush()?,
+        }
+        Ok(())
+    }
+}
+
+/// Key-value pairs read from an exported file
+pub type Records<'a> = Box<dyn Iterator<Item = Result<(Vec<u8>, Vec<u8>)>> + 'a>;
+
+/// Reads the records of an exported file lazily, so files larger than memory can be imported
+pub fn read_records<'a, R: BufRead + 'a>(reader: R, format: ExportFormat) -> Records<'a> {
+    if format == ExportFormat::Json {
+        let records = reader
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| line.as_ref().map_or(true, |line| !line.trim().is_empty()))
+            .map(|(index, line)| {
+                let line_number = index + 1;
+                let record: serde_json::Map<String, serde_json::Value> =
+                    serde_json::from_str(&line?).map_err(|e| invalid_record(line_number, e))?;
+                record_entry(&record, line_number)
+            });
+        return Box::new(records);
+    }
+    let reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter(format))
+        .from_reader(reader);
+    let records = reader.into_records().map(|record| {
+        let record = record.map_err(|e| {
+            let line = e.position().map_or(0, |position| position.line() as usize);
+            invalid_record(line, e)
+        })?;
+        let line = record
+            .position()
+            .map_or(0, |position| position.line() as usize);
+        let (Some(key), Some(value)) = (record.get(0), record.get(1)) else {
+            return Err(invalid_record(line, "expected key and value columns"));
+        };
+        decode_entry(key, value, record.get(2).unwrap_or(UTF8_ENCODING), line)
+    });
+    Box::new(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ExportFormat, RecordWriter, read_records};
+    use crate::error::CliError;
+
+    const ENTRIES: [(&[u8], &[u8]); 6] = [
+        (b"plain", b"value"),
+        (b"comma,key", b"comma,value"),
+        (b"tab\tkey", b"tab\tvalue"),
+        (b"line\r\nkey", b"quoted \"value\"\n"),
+        (b"empty", b""),
+        (b"\xff\x00binary", b"\x00\x01\xfe"),
+    ];
+
+    fn round_trip(format: ExportFormat) -> Vec<(Vec<u8>, Vec<u8>)> {
+        let mut buffer = Vec::new();
+        let mut writer = RecordWriter::new(&mut buffer, format).unwrap();
+        for (key, value) in ENTRIES {
+            writer.write(key, value).unwrap();
+        }
+        writer.finish().unwrap();
+        read_records(buffer.as_slice(), format)
+            .collect::<Result<_, _>>()
+            .unwrap()
+    }
+
+    fn expected() -> Vec<(Vec<u8>, Vec<u8>)> {
+        ENTRIES
+            .iter()
+            .map(|(key, value)| (key.to_vec(), value.to_vec()))
+            .collect()
+    }
+
+    #[test]
+    fn json_round_trips() {
+        assert_eq!(round_trip(ExportFormat::Json), expected());
+    }
+
+    #[test]
+    fn csv_round_trips() {
+        assert_eq!(round_trip(ExportFormat::Csv), expected());
+    }
+
+    #[test]
+    fn tsv_round_trips() {
+        assert_eq!(round_trip(ExportFormat::Tsv), expected());
+    }
+
+    #[test]
+    fn invalid_record_reports_its_line() {
+        let input = "{\"key\":\"a\",\"value\":\"b\"}\nnot json\n";
+        let mut records = read_records(input.as_bytes(), ExportFormat::Json);
+        assert_eq!(
+            records.next().unwrap().unwrap(),
+            (b"a".to_vec(), b"b".to_vec())
+        );
+        assert!(matches!(
+            records.next().unwrap(),
+            Err(CliError::InvalidRecord { line: 2, .. })
+        ));
+    }
+}
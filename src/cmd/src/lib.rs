@@ -3,4 +3,7 @@ pub mod cli_processor;
 pub mod command;
 pub mod db;
 pub mod display;
+pub mod error;
+pub mod export;
 pub mod utility;
+pub mod wal;
@@ -4,14 +4,29 @@ use rocksdb_cli::cli_processor::CliProcessor;
 use rocksdb_cli::command::{Cli, DBCommand};
 use rocksdb_cli::db::DBHelper;
 use rustyrepl::{Repl, ReplCommandProcessor};
-pub fn main() {
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+pub fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
-    let helper = DBHelper::new(&cli.path, cli.readonly);
+    if cli.no_color {
+        colored::control::set_override(false);
+    }
+    let ttl = cli.ttl.map(Duration::from_secs);
+    let prefix_len = (!cli.no_prefix_extractor).then_some(cli.prefix_len);
+    let mut helper = DBHelper::new(&cli.path, cli.readonly, ttl, prefix_len)?;
+    helper.batch_size = cli.batch_size;
+    let helper = Rc::new(RefCell::new(helper));
     let commands = vec![
         "help".into(),
         "list".into(),
         "info".into(),
+        "stats".into(),
+        "set".into(),
         "use".into(),
+        "create-cf".into(),
+        "drop-cf".into(),
+        "copy-cf".into(),
         "keys".into(),
         "contains-key".into(),
         "search-value".into(),
@@ -20,20 +35,40 @@ pub fn main() {
         "exit".into(),
         "put".into(),
         "get".into(),
+        "multi-get".into(),
         "delete".into(),
+        "put-batch".into(),
+        "delete-batch".into(),
+        "delete-range".into(),
+        "compact".into(),
+        "export".into(),
+        "import".into(),
+        "diff".into(),
         "scan".into(),
+        "nth".into(),
+        "count".into(),
+        "ttl-check".into(),
+        "wal-scan".into(),
+        "read-amp".into(),
         "quit".into(),
     ];
-    let cli_helper = CliHelper::new(commands);
+    let cli_helper = CliHelper::new(commands, helper.clone());
+    let processor = CliProcessor::new(helper, cli.output_format);
+    if let Some(line) = &cli.exec {
+        processor.run_line(line)?;
+        return Ok(());
+    }
+    if let Some(script) = &cli.script {
+        return processor.run_script(script, cli.stop_on_error, cli.quiet);
+    }
     println!("RocksDB Interactive Shell");
     println!("Type 'help' for available commands");
-    let processor: Box<dyn ReplCommandProcessor<DBCommand>> = Box::new(CliProcessor::new(helper));
+    let processor: Box<dyn ReplCommandProcessor<DBCommand>> = Box::new(processor);
     let mut repl = Repl::<DBCommand, CliHelper>::new(
         processor,
         Some("./history_file".to_string()),
         Some(cli_helper),
-    )
-    .unwrap();
+    )?;
 
-    repl.process().unwrap();
+    repl.process()
 }
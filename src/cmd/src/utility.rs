@@ -1,7 +1,10 @@
+use crate::db::DBHelper;
 use anyhow::Result;
 use colored::Colorize;
+use regex::Regex;
 use std::fs::File;
 use std::io::{BufWriter, Write};
+use std::time::Duration;
 
 pub fn highlight_pattern(pattern: &str, candidates: Vec<u8>) -> Vec<u8> {
     if pattern.is_empty() {
@@ -28,7 +31,96 @@ pub fn highlight_pattern(pattern: &str, candidates: Vec<u8>) -> Vec<u8> {
     result
 }
 
-pub fn write_output_to_file<T: Iterator<Item = (Vec<u8>, Vec<u8>)>>(key_values: T, file_path: &str) -> Result<()> {
+/// Highlights every match of `regex` in the UTF-8 text of `candidates`
+pub fn highlight_regex(regex: &Regex, candidates: Vec<u8>) -> Vec<u8> {
+    let text = match str::from_utf8(&candidates) {
+        Ok(text) => text,
+        Err(_) => return candidates,
+    };
+    let mut last_index = 0;
+    let mut result = Vec::with_capacity(candidates.len());
+    for matched in regex.find_iter(text) {
+        result.extend(&text.as_bytes()[last_index..matched.start()]);
+        result.extend(format!("{}", matched.as_str().bright_magenta()).into_bytes());
+        last_index = matched.end();
+    }
+    result.extend(&text.as_bytes()[last_index..]);
+    result
+}
+
+/// Formats a duration as days, hours, minutes and seconds, e.g. `1d 2h 3m 4s`
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    let (days, hours, minutes, seconds) = (
+        secs / 86400,
+        secs % 86400 / 3600,
+        secs % 3600 / 60,
+        secs % 60,
+    );
+    if days > 0 {
+        format!("{}d {}h {}m {}s", days, hours, minutes, seconds)
+    } else if hours > 0 {
+        format!("{}h {}m {}s", hours, minutes, seconds)
+    } else if minutes > 0 {
+        format!("{}m {}s", minutes, seconds)
+    } else {
+        format!("{}s", seconds)
+    }
+}
+
+/// Copies `text` to the system clipboard
+#[cfg(feature = "clipboard")]
+pub fn copy_to_clipboard(text: &str) -> Result<()> {
+    let mut clipboard = arboard::Clipboard::new()?;
+    clipboard.set_text(text)?;
+    Ok(())
+}
+
+#[cfg(not(feature = "clipboard"))]
+pub fn copy_to_clipboard(_text: &str) -> Result<()> {
+    anyhow::bail!("Clipboard support is not enabled, rebuild with --features clipboard")
+}
+
+/// Returns the key given on the command line, hex decoded if `hex` is set, or the raw bytes
+/// of `key_file` if set
+pub fn read_key(key: Option<String>, key_file: Option<&str>, hex: bool) -> Result<Vec<u8>> {
+    match (key, key_file) {
+        (_, Some(path)) => Ok(std::fs::read(path)?),
+        (Some(key), None) => Ok(DBHelper::decode_key(&key, hex)?),
+        (None, None) => anyhow::bail!("Either a key or --key-file is required"),
+    }
+}
+
+/// Parses `key<TAB>value` lines, skipping empty ones. Fails listing every malformed line,
+/// so a batch is never written with part of the file missing
+pub fn parse_tab_separated(content: &str) -> Result<Vec<(&str, &str)>> {
+    let mut pairs = Vec::new();
+    let mut malformed = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        if line.is_empty() {
+            continue;
+        }
+        match line.split_once('\t') {
+            Some(pair) => pairs.push(pair),
+            None => malformed.push((index + 1).to_string()),
+        }
+    }
+    if !malformed.is_empty() {
+        anyhow::bail!(
+            "No tab separating key and value on line(s) {}",
+            malformed.join(", ")
+        );
+    }
+    Ok(pairs)
+}
+
+/// Writes `key: value` lines, followed by ` (raw: value)` with the value as stored if
+/// `show_raw` is set
+pub fn write_output_to_file<T: Iterator<Item = (Vec<u8>, Vec<u8>)>>(
+    key_values: T,
+    file_path: &str,
+    show_raw: bool,
+) -> Result<()> {
     let file = File::create(file_path)?;
     let mut writer = BufWriter::new(file);
     for (key, value) in key_values {
@@ -38,9 +130,17 @@ pub fn write_output_to_file<T: Iterator<Item = (Vec<u8>, Vec<u8>)>>(key_values:
                 Ok(es) => es,
                 Err(_) => s.to_string(),
             },
-            Err(_) => format!("[BINARY] {}", hex::encode(value)),
+            Err(_) => format!("[BINARY] {}", hex::encode(&value)),
         };
-        writeln!(writer, "{}: {}", key_str, value_str).unwrap();
+        if show_raw {
+            let raw_str = match std::str::from_utf8(&value) {
+                Ok(s) => s.to_string(),
+                Err(_) => format!("[BINARY] {}", hex::encode(&value)),
+            };
+            writeln!(writer, "{}: {} (raw: {})", key_str, value_str, raw_str).unwrap();
+        } else {
+            writeln!(writer, "{}: {}", key_str, value_str).unwrap();
+        }
     }
     Ok(())
 }
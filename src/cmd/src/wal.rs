@@ -0,0 +1,84 @@
+use crate::error::Result;
+use rocksdb::DB;
+use rocksdb::WriteBatchIterator;
+
+/// A put or delete decoded from a write-ahead log batch
+#[derive(Debug)]
+pub struct WalOp {
+    /// `None` if the decoder skipped operations of the batch, their sequence numbers are taken
+    /// at positions that aren't known
+    pub seq: Option<u64>,
+    pub key: Box<[u8]>,
+    /// `None` for a delete
+    pub value: Option<Box<[u8]>>,
+}
+
+/// A write batch read back from the write-ahead log
+#[derive(Debug)]
+pub struct WalBatch {
+    /// Sequence number of the first operation in the batch
+    pub seq: u64,
+    /// Number of operations recorded in the batch
+    pub count: usize,
+    /// Decoded operations. The rocksdb binding only reports puts and deletes on the default
+    /// column family: it skips merges and single deletes, and stops at the first range delete
+    /// or operation on another column family. So this may hold fewer than `count` entries.
+    pub ops: Vec<WalOp>,
+}
+
+impl WalBatch {
+    /// Sequence number of the last operation in the batch
+    pub fn last_seq(&self) -> u64 {
+        self.seq + (self.count as u64).saturating_sub(1)
+    }
+}
+
+struct OpCollector {
+    next_seq: u64,
+    ops: Vec<WalOp>,
+}
+
+impl WriteBatchIterator for OpCollector {
+    fn put(&mut self, key: Box<[u8]>, value: Box<[u8]>) {
+        self.ops.push(WalOp {
+            seq: Some(self.next_seq),
+            key,
+            value: Some(value),
+        });
+        self.next_seq += 1;
+    }
+
+    fn delete(&mut self, key: Box<[u8]>) {
+        self.ops.push(WalOp {
+            seq: Some(self.next_seq),
+            key,
+            value: None,
+        });
+        self.next_seq += 1;
+    }
+}
+
+/// Reads the write batches still present in the WAL, starting at sequence number `since`.
+/// Writes that were already flushed and whose WAL files were purged are not returned.
+pub fn read_wal(db: &DB, since: u64) -> Result<impl Iterator<Item = Result<WalBatch>>> {
+    let iter = db.get_updates_since(since)?;
+    Ok(iter.map(|item| {
+        let (seq, batch) = item?;
+        let mut collector = OpCollector {
+            next_seq: seq,
+            ops: Vec::with_capacity(batch.len()),
+        };
+        batch.iterate(&mut collector);
+        if collector.ops.len() < batch.len() {
+            // counted from the start of the batch, so wrong after a skipped operation
+            for op in &mut collector.ops {
+                op.seq = None;
+            }
+        }
+        Ok(WalBatch {
+            seq,
+            count: batch.len(),
+            ops: collector.ops,
+        })
+    }))
+}
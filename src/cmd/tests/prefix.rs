@@ -0,0 +1,100 @@
+use rocksdb::{DB, Direction, IteratorMode, ReadOptions, WriteOptions};
+use rocksdb_cli::db::DBHelper;
+
+const KEYS: [&[u8]; 5] = [
+    b"abcd0001-a",
+    b"abcd0001-b",
+    b"abcd0002-a",
+    b"abcd9999-a",
+    b"abce0001-a",
+];
+
+/// A database with the given prefix extractor, its keys share only their first 4 bytes. The
+/// keys are flushed so reads go through the SST files and their prefix blooms
+fn open_db(dir: &tempfile::TempDir, prefix_len: Option<usize>) -> DBHelper {
+    DB::open_default(dir.path()).expect("create the database");
+    let path = dir.path().to_str().expect("UTF-8 temporary path");
+    let helper = DBHelper::new(path, Some(false), None, prefix_len).expect("open the database");
+    for key in KEYS {
+        helper
+            .put(key, b"value", &WriteOptions::default())
+            .expect("put a key");
+    }
+    let cf = helper
+        .db
+        .cf_handle("default")
+        .expect("default column family");
+    helper.db.flush_cf(cf).expect("flush");
+    helper
+}
+
+fn prefix_keys(helper: &DBHelper, prefix: &[u8], reverse: bool) -> Vec<Vec<u8>> {
+    helper
+        .prefix(prefix, false, reverse, 0)
+        .expect("prefix scan")
+        .map(|(key, _)| key)
+        .collect()
+}
+
+/// Keys read from `from` with `prefix_same_as_start`, which only stops at the end of the
+/// prefix when the column family has a prefix extractor
+fn same_prefix_keys(helper: &DBHelper, from: &[u8]) -> Vec<Vec<u8>> {
+    let cf = helper
+        .db
+        .cf_handle("default")
+        .expect("default column family");
+    let mut read_options = ReadOptions::default();
+    read_options.set_prefix_same_as_start(true);
+    helper
+        .db
+        .iterator_cf_opt(
+            cf,
+            read_options,
+            IteratorMode::From(from, Direction::Forward),
+        )
+        .map(|item| item.expect("read an entry").0.into_vec())
+        .collect()
+}
+
+#[test]
+fn prefix_extractor_is_applied_to_the_column_families() {
+    let dir = tempfile::tempdir().unwrap();
+    let helper = open_db(&dir, Some(8));
+    assert_eq!(
+        same_prefix_keys(&helper, b"abcd0001"),
+        vec![b"abcd0001-a".to_vec(), b"abcd0001-b".to_vec()]
+    );
+
+    let dir = tempfile::tempdir().unwrap();
+    let helper = open_db(&dir, None);
+    assert_eq!(same_prefix_keys(&helper, b"abcd0001").len(), KEYS.len());
+}
+
+#[test]
+fn prefix_of_extractor_length_returns_only_matching_keys() {
+    let dir = tempfile::tempdir().unwrap();
+    let helper = open_db(&dir, Some(8));
+    assert_eq!(
+        prefix_keys(&helper, b"abcd0001", false),
+        vec![b"abcd0001-a".to_vec(), b"abcd0001-b".to_vec()]
+    );
+    assert_eq!(
+        prefix_keys(&helper, b"abcd0001", true),
+        vec![b"abcd0001-b".to_vec(), b"abcd0001-a".to_vec()]
+    );
+}
+
+#[test]
+fn prefix_shorter_than_extractor_spans_several_prefixes() {
+    let dir = tempfile::tempdir().unwrap();
+    let helper = open_db(&dir, Some(8));
+    assert_eq!(
+        prefix_keys(&helper, b"abcd", false),
+        vec![
+            b"abcd0001-a".to_vec(),
+            b"abcd0001-b".to_vec(),
+            b"abcd0002-a".to_vec(),
+            b"abcd9999-a".to_vec(),
+        ]
+    );
+}